@@ -619,11 +619,35 @@ impl BackupRange {
 }
 
 #[derive(Clone)]
-pub struct ConfigManager(Arc<RwLock<BackupConfig>>);
+pub struct ConfigManager(Arc<RwLock<BackupConfig>>, Limiter);
+
+impl ConfigManager {
+    fn new(config: BackupConfig) -> ConfigManager {
+        let limiter = Limiter::new(rate_limit_speed(&config));
+        ConfigManager(Arc::new(RwLock::new(config)), limiter)
+    }
+
+    /// Returns the limiter enforcing `backup.rate_limit`. It is shared by all
+    /// backup requests that do not carry their own rate limit, so refreshing
+    /// its speed limit takes effect on already running tasks.
+    fn rate_limiter(&self) -> Limiter {
+        self.1.clone()
+    }
+}
+
+fn rate_limit_speed(config: &BackupConfig) -> f64 {
+    if config.rate_limit.0 > 0 {
+        config.rate_limit.0 as f64
+    } else {
+        f64::INFINITY
+    }
+}
 
 impl online_config::ConfigManager for ConfigManager {
     fn dispatch(&mut self, change: online_config::ConfigChange) -> online_config::Result<()> {
-        self.0.write().unwrap().update(change)
+        self.0.write().unwrap().update(change)?;
+        self.1.set_speed_limit(rate_limit_speed(&self.0.read().unwrap()));
+        Ok(())
     }
 }
 
@@ -869,7 +893,7 @@ impl<E: Engine, R: RegionInfoProvider + Clone + 'static> Endpoint<E, R> {
     ) -> Endpoint<E, R> {
         let pool = ControlThreadPool::new();
         let rt = utils::create_tokio_runtime(config.io_thread_size, "backup-io").unwrap();
-        let config_manager = ConfigManager(Arc::new(RwLock::new(config)));
+        let config_manager = ConfigManager::new(config);
         let softlimit = SoftLimitKeeper::new(config_manager.clone());
         rt.spawn(softlimit.clone().run());
         Endpoint {
@@ -906,6 +930,7 @@ impl<E: Engine, R: RegionInfoProvider + Clone + 'static> Endpoint<E, R> {
                     .linux_user
                     .clone(),
             },
+            ..Default::default()
         }
     }
 
@@ -1082,7 +1107,13 @@ impl<E: Engine, R: RegionInfoProvider + Clone + 'static> Endpoint<E, R> {
     }
 
     pub fn handle_backup_task(&self, task: Task) {
-        let Task { request, resp } = task;
+        let Task { mut request, resp } = task;
+        if request.limiter.speed_limit().is_infinite() {
+            // Requests without an explicit rate limit share the store-wide
+            // limiter so that `backup.rate_limit` changes apply to them even
+            // after the task has started.
+            request.limiter = self.config_manager.rate_limiter();
+        }
         let codec = KeyValueCodec::new(request.is_raw_kv, self.api_version, request.dst_api_ver);
         if !codec.check_backup_api_version(&request.start_key, &request.end_key) {
             let mut response = BackupResponse::default();
@@ -2032,6 +2063,66 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_handle_incremental_backup() {
+        let (tmp, endpoint) = new_endpoint();
+        let mut engine = endpoint.engine.clone();
+        endpoint
+            .region_info
+            .set_regions(vec![(b"".to_vec(), b"5".to_vec(), 1)]);
+
+        let mut ts = TimeStamp::new(1);
+        let mut alloc_ts = || *ts.incr();
+        for i in 0..10u8 {
+            let start = alloc_ts();
+            let commit = alloc_ts();
+            let key = format!("{}", i);
+            must_prewrite_put(
+                &mut engine,
+                key.as_bytes(),
+                key.as_bytes(),
+                key.as_bytes(),
+                start,
+            );
+            must_commit(&mut engine, key.as_bytes(), start, commit);
+        }
+        let last_backup_ts = alloc_ts();
+        // Only keys 0..4 are touched after the last backup.
+        for i in 0..5u8 {
+            let start = alloc_ts();
+            let commit = alloc_ts();
+            let key = format!("{}", i);
+            must_prewrite_put(&mut engine, key.as_bytes(), b"new", key.as_bytes(), start);
+            must_commit(&mut engine, key.as_bytes(), start, commit);
+        }
+        let now = alloc_ts();
+
+        let backup = |start_version: TimeStamp, end_version: TimeStamp| {
+            let mut req = BackupRequest::default();
+            req.set_start_key(vec![]);
+            req.set_end_key(vec![b'5']);
+            req.set_start_version(start_version.into_inner());
+            req.set_end_version(end_version.into_inner());
+            req.set_storage_backend(make_local_backend(&make_unique_dir(tmp.path())));
+            let (tx, rx) = unbounded();
+            let (task, _) = Task::new(req, tx).unwrap();
+            endpoint.handle_backup_task(task);
+            let (resp, _) = block_on(rx.into_future());
+            let resp = resp.unwrap();
+            assert!(!resp.has_error(), "{:?}", resp);
+            resp.get_files()
+                .iter()
+                .map(|f| f.get_total_kvs())
+                .sum::<u64>()
+        };
+
+        // A full backup emits the latest version of every key while the
+        // incremental one only emits entries committed in
+        // (last_backup_ts, now].
+        assert_eq!(backup(TimeStamp::zero(), now), 10);
+        assert_eq!(backup(last_backup_ts, now), 5);
+    }
+
     fn generate_test_raw_key(idx: u64, api_ver: ApiVersion) -> String {
         // first key is an empty key for testing purposes
         let mut key = if idx == 0 {
@@ -2488,6 +2579,51 @@ pub mod tests {
         std::thread::sleep(Duration::from_millis(150));
     }
 
+    #[test]
+    fn test_backup_rate_limit_refresh() {
+        use online_config::{ConfigManager as _, OnlineConfig};
+
+        let cfg = BackupConfig::default();
+        let mut cfg_manager = super::ConfigManager::new(cfg.clone());
+        let limiter = cfg_manager.rate_limiter();
+        assert!(limiter.speed_limit().is_infinite());
+
+        // Throttling takes effect on the limiter handed out before.
+        let mut new_cfg = cfg.clone();
+        new_cfg.rate_limit = ReadableSize::mb(10);
+        cfg_manager.dispatch(cfg.diff(&new_cfg)).unwrap();
+        assert_eq!(limiter.speed_limit(), ReadableSize::mb(10).0 as f64);
+
+        // Setting the limit back to 0 unthrottles.
+        cfg_manager.dispatch(new_cfg.diff(&cfg)).unwrap();
+        assert!(limiter.speed_limit().is_infinite());
+
+        // Requests without an explicit rate limit fall back to the shared
+        // limiter, others keep their own.
+        let (tmp, endpoint) = new_endpoint();
+        let mut req = BackupRequest::default();
+        req.set_start_key(vec![b'a']);
+        req.set_end_key(vec![b'z']);
+        req.set_storage_backend(make_local_backend(tmp.path()));
+        let (tx, _) = unbounded();
+        let (task, _) = Task::new(req.clone(), tx.clone()).unwrap();
+        endpoint
+            .get_config_manager()
+            .dispatch(cfg.diff(&new_cfg))
+            .unwrap();
+        endpoint.handle_backup_task(task);
+        assert_eq!(
+            endpoint.get_config_manager().rate_limiter().speed_limit(),
+            ReadableSize::mb(10).0 as f64
+        );
+        req.set_rate_limit(ReadableSize::mb(20).0);
+        let (task, _) = Task::new(req, tx).unwrap();
+        assert_eq!(
+            task.request.limiter.speed_limit(),
+            ReadableSize::mb(20).0 as f64
+        );
+    }
+
     #[test]
     fn test_backup_file_name() {
         let region = metapb::Region::default();
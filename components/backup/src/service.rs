@@ -239,4 +239,19 @@ mod tests {
         // A stopped remote must not cause panic.
         endpoint.handle_backup_task(task);
     }
+
+    #[test]
+    fn test_invalid_request_rejected() {
+        let (_server, client, mut rx) = new_rpc_suite();
+
+        // An invalid column family fails task creation, so the stream must
+        // end with an error and nothing is scheduled.
+        let mut req = BackupRequest::default();
+        req.set_end_key(vec![b'5']);
+        req.set_cf("invalid_cf".to_owned());
+        let mut stream = client.backup(&req).unwrap();
+        let res = futures::executor::block_on(stream.next());
+        res.unwrap().unwrap_err();
+        rx.recv_timeout(Duration::from_millis(100)).unwrap_err();
+    }
 }
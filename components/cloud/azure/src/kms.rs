@@ -281,6 +281,7 @@ mod tests {
                 endpoint: String::new(),
             },
             azure: Some(err_azure_cfg.clone()),
+            vault: None,
         };
         AzureKms::new(err_config.clone()).unwrap_err();
         let azure_cfg = SubConfigAzure {
@@ -289,6 +290,7 @@ mod tests {
         };
         let config = Config {
             azure: Some(azure_cfg),
+            vault: None,
             ..err_config
         };
         let azure_kms = AzureKms::new(config).unwrap();
@@ -324,6 +326,7 @@ mod tests {
                 endpoint: String::new(),
             },
             azure: Some(azure_cfg),
+            vault: None,
         };
         if config.vendor != STORAGE_VENDOR_NAME_AZURE {
             AzureKms::new(config).unwrap();
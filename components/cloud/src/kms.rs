@@ -35,11 +35,22 @@ pub struct SubConfigAzure {
     pub client_secret: Option<String>,
 }
 
+/// Configurations for HashiCorp Vault's transit secrets engine.
+#[derive(Debug, Default, Clone)]
+pub struct SubConfigVault {
+    /// Token used to authenticate against Vault.
+    pub token: String,
+    /// Path of a PEM encoded CA certificate to verify the Vault server with,
+    /// in addition to the system roots.
+    pub ca_cert: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub key_id: KeyId,
     pub location: Location,
     pub azure: Option<SubConfigAzure>,
+    pub vault: Option<SubConfigVault>,
     pub vendor: String,
 }
 
@@ -52,6 +63,7 @@ impl Config {
                 endpoint: mk.endpoint,
             },
             azure: None,
+            vault: None,
             vendor: mk.vendor,
         })
     }
@@ -61,6 +73,12 @@ impl Config {
         cfg.azure = Some(azure_kms_cfg);
         Ok(cfg)
     }
+
+    pub fn from_vault_kms_config(mk: MasterKeyKms, vault_kms_cfg: SubConfigVault) -> Result<Self> {
+        let mut cfg = Config::from_proto(mk)?;
+        cfg.vault = Some(vault_kms_cfg);
+        Ok(cfg)
+    }
 }
 
 #[derive(PartialEq, Debug, Clone, Deref)]
@@ -0,0 +1,224 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use async_trait::async_trait;
+use cloud::{
+    error::{Error, KmsError, Result},
+    kms::{Config, DataKeyPair, EncryptedKey, KmsProvider},
+};
+use hyper::{client::HttpConnector, Body, Client, Method, Request, StatusCode};
+use hyper_tls::HttpsConnector;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tikv_util::box_err;
+
+use crate::STORAGE_VENDOR_NAME_VAULT;
+
+// Vault wraps every secret payload in a `data` object.
+#[derive(Deserialize)]
+struct Secret<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct DataKeyResponse {
+    // Base64 encoded plaintext data key.
+    plaintext: String,
+    // Opaque ciphertext, e.g. "vault:v1:...". Only Vault can decrypt it.
+    ciphertext: String,
+}
+
+#[derive(Serialize)]
+struct DecryptRequest<'a> {
+    ciphertext: &'a str,
+}
+
+#[derive(Deserialize)]
+struct DecryptResponse {
+    plaintext: String,
+}
+
+/// A `KmsProvider` backed by the transit secrets engine of a HashiCorp Vault
+/// server. Data keys are generated by `transit/datakey` and unwrapped by
+/// `transit/decrypt`, so the named transit key never leaves Vault.
+pub struct VaultKms {
+    client: Client<HttpsConnector<HttpConnector>>,
+    endpoint: String,
+    key_id: String,
+    token: String,
+}
+
+// Do not expose the token in a debug print.
+impl std::fmt::Debug for VaultKms {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VaultKms")
+            .field("endpoint", &self.endpoint)
+            .field("key_id", &self.key_id)
+            .finish()
+    }
+}
+
+impl VaultKms {
+    pub fn new(config: Config) -> Result<Self> {
+        let vault_cfg = config
+            .vault
+            .ok_or_else(|| Error::KmsError(KmsError::Other(box_err!("missing vault config"))))?;
+        if config.location.endpoint.is_empty() {
+            return Err(Error::KmsError(KmsError::Other(box_err!(
+                "vault endpoint must not be empty"
+            ))));
+        }
+        if vault_cfg.token.is_empty() {
+            return Err(Error::KmsError(KmsError::Other(box_err!(
+                "vault token must not be empty"
+            ))));
+        }
+
+        let mut tls = native_tls::TlsConnector::builder();
+        if let Some(path) = &vault_cfg.ca_cert {
+            let pem = std::fs::read(path)?;
+            let cert = native_tls::Certificate::from_pem(&pem)
+                .map_err(|e| Error::KmsError(KmsError::Other(box_err!("bad ca cert: {}", e))))?;
+            tls.add_root_certificate(cert);
+        }
+        let tls = tokio_native_tls::TlsConnector::from(
+            tls.build()
+                .map_err(|e| Error::KmsError(KmsError::Other(box_err!("init tls: {}", e))))?,
+        );
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        let client = Client::builder().build(HttpsConnector::from((http, tls)));
+
+        Ok(VaultKms {
+            client,
+            endpoint: config.location.endpoint.trim_end_matches('/').to_owned(),
+            key_id: config.key_id.into_inner(),
+            token: vault_cfg.token,
+        })
+    }
+
+    async fn transit_request<T: DeserializeOwned>(&self, op: &str, body: Vec<u8>) -> Result<T> {
+        let uri = format!("{}/v1/transit/{}/{}", self.endpoint, op, self.key_id);
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(&uri)
+            .header("X-Vault-Token", &self.token)
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .map_err(|e| Error::KmsError(KmsError::Other(box_err!("build request: {}", e))))?;
+        let resp = self
+            .client
+            .request(req)
+            .await
+            .map_err(|e| Error::ApiInternal(box_err!("vault request failed: {}", e)))?;
+        let status = resp.status();
+        let body = hyper::body::to_bytes(resp.into_body())
+            .await
+            .map_err(|e| Error::ApiInternal(box_err!("read vault response: {}", e)))?;
+        match status {
+            StatusCode::OK => {
+                let secret: Secret<T> = serde_json::from_slice(&body).map_err(|e| {
+                    Error::KmsError(KmsError::Other(box_err!("parse vault response: {}", e)))
+                })?;
+                Ok(secret.data)
+            }
+            StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => Err(Error::ApiAuthentication(
+                box_err!("vault returned {}", status),
+            )),
+            StatusCode::NOT_FOUND => Err(Error::ApiNotFound(box_err!(
+                "vault transit key {} not found",
+                self.key_id
+            ))),
+            _ => Err(Error::ApiInternal(box_err!(
+                "vault returned {}: {}",
+                status,
+                String::from_utf8_lossy(&body)
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl KmsProvider for VaultKms {
+    async fn generate_data_key(&self) -> Result<DataKeyPair> {
+        let resp: DataKeyResponse = self
+            .transit_request("datakey/plaintext", Vec::new())
+            .await?;
+        let plaintext = base64::decode(&resp.plaintext)
+            .map_err(|e| Error::KmsError(KmsError::Other(box_err!("bad plaintext key: {}", e))))?;
+        Ok(DataKeyPair {
+            plaintext: cloud::kms::PlainKey::new(plaintext, cloud::kms::CryptographyType::AesGcm256)
+                .map_err(|e| Error::KmsError(KmsError::Other(box_err!("{}", e))))?,
+            encrypted: EncryptedKey::new(resp.ciphertext.into_bytes())?,
+        })
+    }
+
+    async fn decrypt_data_key(&self, data_key: &EncryptedKey) -> Result<Vec<u8>> {
+        let ciphertext = std::str::from_utf8(data_key).map_err(|e| {
+            Error::KmsError(KmsError::WrongMasterKey(box_err!("bad ciphertext: {}", e)))
+        })?;
+        let body = serde_json::to_vec(&DecryptRequest { ciphertext })
+            .map_err(|e| Error::KmsError(KmsError::Other(box_err!("serialize request: {}", e))))?;
+        let resp: DecryptResponse = self.transit_request("decrypt", body).await?;
+        base64::decode(&resp.plaintext)
+            .map_err(|e| Error::KmsError(KmsError::Other(box_err!("bad plaintext key: {}", e))))
+    }
+
+    fn name(&self) -> &str {
+        STORAGE_VENDOR_NAME_VAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cloud::kms::{KeyId, Location, SubConfigVault};
+    use matches::assert_matches;
+
+    use super::*;
+
+    fn new_config() -> Config {
+        Config {
+            key_id: KeyId::new("tikv-master-key".to_owned()).unwrap(),
+            location: Location {
+                region: "".to_owned(),
+                endpoint: "https://vault.example.com:8200".to_owned(),
+            },
+            azure: None,
+            vault: Some(SubConfigVault {
+                token: "s.token".to_owned(),
+                ca_cert: None,
+            }),
+            vendor: STORAGE_VENDOR_NAME_VAULT.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_new_vault_kms() {
+        let kms = VaultKms::new(new_config()).unwrap();
+        assert_eq!(kms.name(), STORAGE_VENDOR_NAME_VAULT);
+        // The endpoint is normalized without a trailing slash.
+        assert_eq!(kms.endpoint, "https://vault.example.com:8200");
+    }
+
+    #[test]
+    fn test_new_vault_kms_invalid_config() {
+        let mut missing_vault = new_config();
+        missing_vault.vault = None;
+        assert_matches!(
+            VaultKms::new(missing_vault).unwrap_err(),
+            Error::KmsError(KmsError::Other(_))
+        );
+
+        let mut empty_token = new_config();
+        empty_token.vault.as_mut().unwrap().token.clear();
+        assert_matches!(
+            VaultKms::new(empty_token).unwrap_err(),
+            Error::KmsError(KmsError::Other(_))
+        );
+
+        let mut empty_endpoint = new_config();
+        empty_endpoint.location.endpoint.clear();
+        assert_matches!(
+            VaultKms::new(empty_endpoint).unwrap_err(),
+            Error::KmsError(KmsError::Other(_))
+        );
+    }
+}
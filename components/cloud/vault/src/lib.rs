@@ -0,0 +1,7 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+mod kms;
+
+pub use kms::VaultKms;
+
+pub const STORAGE_VENDOR_NAME_VAULT: &str = "vault";
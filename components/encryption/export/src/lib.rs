@@ -11,10 +11,12 @@ pub use encryption::KmsBackend;
 pub use encryption::{
     clean_up_dir, clean_up_trash, trash_dir_all, AzureConfig, Backend, DataKeyImporter,
     DataKeyManager, DataKeyManagerArgs, DecrypterReader, EncryptionConfig, Error, FileConfig, Iv,
-    KmsConfig, MasterKeyConfig, Result,
+    KmsConfig, MasterKeyConfig, Result, VaultConfig,
 };
 use encryption::{cloud_convert_error, FileBackend, PlaintextBackend};
 use tikv_util::{box_err, error, info};
+#[cfg(feature = "cloud-vault")]
+use vault::{VaultKms, STORAGE_VENDOR_NAME_VAULT};
 
 pub fn data_key_manager_from_config(
     config: &EncryptionConfig,
@@ -70,6 +72,21 @@ pub fn create_cloud_backend(config: &KmsConfig) -> Result<Box<dyn Backend>> {
             );
             Ok(Box::new(KmsBackend::new(keyvault_provider)?) as Box<dyn Backend>)
         }
+        #[cfg(feature = "cloud-vault")]
+        STORAGE_VENDOR_NAME_VAULT => {
+            if config.vault.is_none() {
+                return Err(Error::Other(box_err!(
+                    "invalid configurations for Vault KMS"
+                )));
+            }
+            let (mk, vault_kms_cfg) = config.clone().convert_to_vault_kms_config();
+            let conf = CloudConfig::from_vault_kms_config(mk, vault_kms_cfg)
+                .map_err(cloud_convert_error("vault from proto".to_owned()))?;
+            let kms_provider = Box::new(
+                VaultKms::new(conf).map_err(cloud_convert_error("new Vault KMS".to_owned()))?,
+            );
+            Ok(Box::new(KmsBackend::new(kms_provider)?) as Box<dyn Backend>)
+        }
         provider => Err(Error::Other(box_err!("provider not found {}", provider))),
     }
 }
@@ -88,6 +105,19 @@ fn create_backend_inner(config: &MasterKeyConfig) -> Result<Box<dyn Backend>> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_kms_cloud_backend_unknown_vendor() {
+        let config = KmsConfig {
+            key_id: "key_id".to_owned(),
+            region: "region".to_owned(),
+            endpoint: "endpoint".to_owned(),
+            vendor: "no-such-vendor".to_owned(),
+            azure: None,
+            vault: None,
+        };
+        create_cloud_backend(&config).unwrap_err();
+    }
+
     #[test]
     #[cfg(feature = "cloud-azure")]
     fn test_kms_cloud_backend_azure() {
@@ -105,6 +135,7 @@ mod tests {
                 client_secret: Some("client_secret".to_owned()),
                 ..AzureConfig::default()
             }),
+            vault: None,
         };
         let invalid_config = KmsConfig {
             azure: None,
@@ -114,4 +145,27 @@ mod tests {
         let backend = create_cloud_backend(&config).unwrap();
         assert!(backend.is_secure());
     }
+
+    #[test]
+    #[cfg(feature = "cloud-vault")]
+    fn test_kms_cloud_backend_vault() {
+        let config = KmsConfig {
+            key_id: "tikv-master-key".to_owned(),
+            region: "".to_owned(),
+            endpoint: "https://vault.example.com:8200".to_owned(),
+            vendor: STORAGE_VENDOR_NAME_VAULT.to_owned(),
+            azure: None,
+            vault: Some(VaultConfig {
+                token: "s.token".to_owned(),
+                ca_cert: None,
+            }),
+        };
+        let invalid_config = KmsConfig {
+            vault: None,
+            ..config.clone()
+        };
+        create_cloud_backend(&invalid_config).unwrap_err();
+        let backend = create_cloud_backend(&config).unwrap();
+        assert!(backend.is_secure());
+    }
 }
@@ -1,6 +1,6 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
-use cloud::kms::SubConfigAzure;
+use cloud::kms::{SubConfigAzure, SubConfigVault};
 use kvproto::encryptionpb::{EncryptionMethod, MasterKeyKms};
 use online_config::OnlineConfig;
 use serde_derive::{Deserialize, Serialize};
@@ -16,6 +16,10 @@ pub struct EncryptionConfig {
     pub data_encryption_method: EncryptionMethod,
     #[online_config(skip)]
     pub data_key_rotation_period: ReadableDuration,
+    /// Also rotate the data key once this many files have been encrypted with
+    /// it, regardless of its age. 0 means no volume-based rotation.
+    #[online_config(skip)]
+    pub data_key_rotation_file_threshold: u64,
     #[online_config(skip)]
     pub enable_file_dictionary_log: bool,
     #[online_config(skip)]
@@ -31,6 +35,7 @@ impl Default for EncryptionConfig {
         EncryptionConfig {
             data_encryption_method: EncryptionMethod::Plaintext,
             data_key_rotation_period: ReadableDuration::days(7),
+            data_key_rotation_file_threshold: 0,
             // The option is available since TiKV 4.0.9.
             enable_file_dictionary_log: true,
             file_dictionary_rewrite_threshold: 1000000,
@@ -84,6 +89,28 @@ impl std::fmt::Debug for AzureConfig {
     }
 }
 
+/// Configurations for HashiCorp Vault's transit secrets engine. The Vault
+/// server address goes into `KmsConfig::endpoint` and the transit key name
+/// into `KmsConfig::key_id`.
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+#[serde(rename_all = "kebab-case")]
+pub struct VaultConfig {
+    /// Token used to authenticate against Vault.
+    pub token: String,
+    /// Path of a PEM encoded CA certificate to verify the Vault server with,
+    /// in addition to the system roots.
+    pub ca_cert: Option<String>,
+}
+
+impl std::fmt::Debug for VaultConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VaultConfig")
+            .field("ca_cert", &self.ca_cert)
+            .finish()
+    }
+}
+
 #[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq, OnlineConfig)]
 #[serde(default)]
 #[serde(rename_all = "kebab-case")]
@@ -95,6 +122,9 @@ pub struct KmsConfig {
     // followings are used for Azure Kms
     #[online_config(skip)]
     pub azure: Option<AzureConfig>,
+    // followings are used for Vault Kms
+    #[online_config(skip)]
+    pub vault: Option<VaultConfig>,
 }
 
 impl KmsConfig {
@@ -132,6 +162,24 @@ impl KmsConfig {
         };
         (mk, azure_kms_cfg)
     }
+
+    pub fn convert_to_vault_kms_config(self) -> (MasterKeyKms, SubConfigVault) {
+        let vault_kms_cfg = {
+            let cfg = self.vault.unwrap();
+            SubConfigVault {
+                token: cfg.token,
+                ca_cert: cfg.ca_cert,
+            }
+        };
+        let mk = MasterKeyKms {
+            key_id: self.key_id,
+            region: self.region,
+            endpoint: self.endpoint,
+            vendor: self.vendor,
+            ..MasterKeyKms::default()
+        };
+        (mk, vault_kms_cfg)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -231,11 +279,37 @@ mod encryption_method_serde {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_encryption_method_serde() {
+        #[derive(Serialize, Deserialize)]
+        struct MethodOnly {
+            #[serde(with = "encryption_method_serde")]
+            method: EncryptionMethod,
+        }
+
+        for (text, method) in [
+            ("plaintext", EncryptionMethod::Plaintext),
+            ("aes128-ctr", EncryptionMethod::Aes128Ctr),
+            ("aes192-ctr", EncryptionMethod::Aes192Ctr),
+            ("aes256-ctr", EncryptionMethod::Aes256Ctr),
+            ("sm4-ctr", EncryptionMethod::Sm4Ctr),
+        ] {
+            let cfg: MethodOnly = toml::from_str(&format!("method = \"{}\"", text)).unwrap();
+            assert_eq!(cfg.method, method);
+            assert_eq!(
+                toml::to_string(&cfg).unwrap().trim(),
+                format!("method = \"{}\"", text)
+            );
+        }
+        toml::from_str::<MethodOnly>("method = \"des-cbc\"").unwrap_err();
+    }
+
     #[test]
     fn test_kms_config() {
         let kms_config = EncryptionConfig {
             data_encryption_method: EncryptionMethod::Aes128Ctr,
             data_key_rotation_period: ReadableDuration::days(14),
+            data_key_rotation_file_threshold: 0,
             master_key: MasterKeyConfig::Kms {
                 config: KmsConfig {
                     key_id: "key_id".to_owned(),
@@ -243,6 +317,7 @@ mod tests {
                     endpoint: "endpoint".to_owned(),
                     vendor: "".to_owned(),
                     azure: None,
+                    vault: None,
                 },
             },
             previous_master_key: MasterKeyConfig::Plaintext,
@@ -264,6 +339,23 @@ mod tests {
                         hsm_url: "hsm_url".to_owned(),
                         ..AzureConfig::default()
                     }),
+                    vault: None,
+                },
+            },
+            ..kms_config.clone()
+        };
+        let kms_config_vault = EncryptionConfig {
+            master_key: MasterKeyConfig::Kms {
+                config: KmsConfig {
+                    key_id: "tikv-master-key".to_owned(),
+                    region: "".to_owned(),
+                    endpoint: "https://vault.example.com:8200".to_owned(),
+                    vendor: "vault".to_owned(),
+                    azure: None,
+                    vault: Some(VaultConfig {
+                        token: "s.token".to_owned(),
+                        ca_cert: Some("/path/to/ca.pem".to_owned()),
+                    }),
                 },
             },
             ..kms_config.clone()
@@ -306,7 +398,31 @@ mod tests {
             [previous-master-key]
             type = 'plaintext'
         "#;
-        for (kms_cfg, kms_str) in [(kms_config, kms_str), (kms_config_azure, kms_str_azure)] {
+        // KMS with vault
+        let kms_str_vault = r#"
+            data-encryption-method = 'aes128-ctr'
+            data-key-rotation-period = '14d'
+            enable-file-dictionary-log = true
+            file-dictionary-rewrite-threshold = 1000000
+
+            [master-key]
+            type = 'kms'
+            key-id = 'tikv-master-key'
+            endpoint = 'https://vault.example.com:8200'
+            vendor = 'vault'
+
+            [master-key.vault]
+            token = 's.token'
+            ca-cert = '/path/to/ca.pem'
+
+            [previous-master-key]
+            type = 'plaintext'
+        "#;
+        for (kms_cfg, kms_str) in [
+            (kms_config, kms_str),
+            (kms_config_azure, kms_str_azure),
+            (kms_config_vault, kms_str_vault),
+        ] {
             let cfg: EncryptionConfig = toml::from_str(kms_str).unwrap();
             assert_eq!(
                 cfg,
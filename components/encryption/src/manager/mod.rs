@@ -2,7 +2,7 @@
 
 use std::{
     collections::hash_map::Entry,
-    io::{self, Error as IoError, ErrorKind, Result as IoResult},
+    io::{self, Error as IoError, ErrorKind, Read, Result as IoResult, Write},
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicU64, Ordering},
@@ -49,7 +49,11 @@ struct Dicts {
     // write it back to `key_dict`. Reader should always use this atomic, instead of
     // key_dict.current_key_id, since the latter can reflect an update-in-progress key.
     current_key_id: AtomicU64,
+    // Number of files encrypted with the current data key, used to trigger
+    // volume-based rotation. See `maybe_rotate_data_key`.
+    current_key_files: AtomicU64,
     rotation_period: Duration,
+    rotation_file_threshold: u64,
     base: PathBuf,
 }
 
@@ -57,6 +61,7 @@ impl Dicts {
     fn new(
         path: &str,
         rotation_period: Duration,
+        rotation_file_threshold: u64,
         enable_file_dictionary_log: bool,
         file_dictionary_rewrite_threshold: u64,
     ) -> Result<Dicts> {
@@ -74,7 +79,9 @@ impl Dicts {
             }),
             key_dict_file_lock: Mutex::new(()),
             current_key_id: AtomicU64::new(0),
+            current_key_files: AtomicU64::new(0),
             rotation_period,
+            rotation_file_threshold,
             base: Path::new(path).to_owned(),
         })
     }
@@ -82,6 +89,7 @@ impl Dicts {
     fn open(
         path: &str,
         rotation_period: Duration,
+        rotation_file_threshold: u64,
         master_key: &dyn Backend,
         enable_file_dictionary_log: bool,
         file_dictionary_rewrite_threshold: u64,
@@ -107,9 +115,15 @@ impl Dicts {
                 let mut key_dict = KeyDictionary::default();
                 key_dict.merge_from_bytes(&key_bytes)?;
                 let current_key_id = AtomicU64::new(key_dict.current_key_id);
+                let current_key_files = file_dict
+                    .files
+                    .values()
+                    .filter(|f| f.key_id == key_dict.current_key_id)
+                    .count() as u64;
 
                 ENCRYPTION_DATA_KEY_GAUGE.set(key_dict.keys.len() as _);
                 ENCRYPTION_FILE_NUM_GAUGE.set(file_dict.files.len() as _);
+                ENCRYPTION_DATA_KEY_FILE_NUM_GAUGE.set(current_key_files as _);
 
                 Ok(Some(Dicts {
                     file_dict: Mutex::new(file_dict),
@@ -117,7 +131,9 @@ impl Dicts {
                     key_dict: Mutex::new(key_dict),
                     key_dict_file_lock: Mutex::default(),
                     current_key_id,
+                    current_key_files: AtomicU64::new(current_key_files),
                     rotation_period,
+                    rotation_file_threshold,
                     base: base.to_owned(),
                 }))
             }
@@ -220,6 +236,8 @@ impl Dicts {
         ENCRYPTION_FILE_NUM_GAUGE.set(file_num);
 
         if method != EncryptionMethod::Plaintext {
+            let key_files = self.current_key_files.fetch_add(1, Ordering::SeqCst) + 1;
+            ENCRYPTION_DATA_KEY_FILE_NUM_GAUGE.set(key_files as _);
             debug!("new encrypted file";
                   "fname" => fname,
                   "method" => format!("{:?}", method),
@@ -308,6 +326,9 @@ impl Dicts {
         self.save_key_dict(master_key)?;
         // Update current data key id.
         self.current_key_id.store(key_id, Ordering::SeqCst);
+        // The new key has not encrypted any file yet.
+        self.current_key_files.store(0, Ordering::SeqCst);
+        ENCRYPTION_DATA_KEY_FILE_NUM_GAUGE.set(0);
         Ok(true)
     }
 
@@ -327,13 +348,21 @@ impl Dicts {
             //   2. the current data key was exposed and the new master key is secure.
             if method == key.method && !(key.was_exposed && master_key.is_secure()) {
                 let creation_time = UNIX_EPOCH + Duration::from_secs(key.creation_time);
+                let key_files = self.current_key_files.load(Ordering::SeqCst);
+                let volume_exceeded =
+                    self.rotation_file_threshold > 0 && key_files >= self.rotation_file_threshold;
                 match now.duration_since(creation_time) {
                     Ok(duration) => {
-                        if self.rotation_period > duration {
+                        ENCRYPTION_DATA_KEY_AGE_GAUGE.set(duration.as_secs() as _);
+                        if self.rotation_period > duration && !volume_exceeded {
                             debug!("current data key creation time is within rotation period";
                                 "now" => ?now, "creation_time" => ?creation_time);
                             return Ok(());
                         }
+                        if volume_exceeded {
+                            info!("current data key reached the file volume threshold";
+                                "files" => key_files, "threshold" => self.rotation_file_threshold);
+                        }
                     }
                     Err(e) => {
                         warn!("data key rotate duraion overflow, generate a new data key";
@@ -405,7 +434,7 @@ enum RotateTask {
 fn run_background_rotate_work(
     dict: Arc<Dicts>,
     method: EncryptionMethod,
-    master_key: &dyn Backend,
+    master_key: Arc<Mutex<Box<dyn Backend>>>,
     rx: channel::Receiver<RotateTask>,
 ) {
     let check_period = std::cmp::min(
@@ -417,7 +446,8 @@ fn run_background_rotate_work(
         select! {
             recv(tick(check_period)) -> _ => {
                 info!("Try to rotate data key, current method:{:?}", method);
-                dict.maybe_rotate_data_key(method, master_key)
+                let master_key = master_key.lock().unwrap();
+                dict.maybe_rotate_data_key(method, master_key.as_ref())
                     .expect("Rotating key operation encountered error in the background worker");
             },
             recv(rx) -> r => {
@@ -427,7 +457,8 @@ fn run_background_rotate_work(
                         return;
                     }
                     Ok(RotateTask::Save(tx)) => {
-                        dict.save_key_dict(master_key).expect("Saving key dict encountered error in the background worker");
+                        let master_key = master_key.lock().unwrap();
+                        dict.save_key_dict(master_key.as_ref()).expect("Saving key dict encountered error in the background worker");
                         tx.send(()).unwrap();
                     }
                 }
@@ -449,6 +480,9 @@ fn generate_data_key(method: EncryptionMethod) -> (u64, Vec<u8>) {
 pub struct DataKeyManager {
     dicts: Arc<Dicts>,
     method: EncryptionMethod,
+    // Shared with the background rotation worker so the master key can be
+    // replaced online, see `rotate_master_key`.
+    master_key: Arc<Mutex<Box<dyn Backend>>>,
     rotate_tx: channel::Sender<RotateTask>,
     background_worker: Option<JoinHandle<()>>,
 }
@@ -457,6 +491,7 @@ pub struct DataKeyManager {
 pub struct DataKeyManagerArgs {
     pub method: EncryptionMethod,
     pub rotation_period: Duration,
+    pub rotation_file_threshold: u64,
     pub enable_file_dictionary_log: bool,
     pub file_dictionary_rewrite_threshold: u64,
     pub dict_path: String,
@@ -471,6 +506,7 @@ impl DataKeyManagerArgs {
             dict_path: dict_path.to_string(),
             method: config.data_encryption_method,
             rotation_period: config.data_key_rotation_period.into(),
+            rotation_file_threshold: config.data_key_rotation_file_threshold,
             enable_file_dictionary_log: config.enable_file_dictionary_log,
             file_dictionary_rewrite_threshold: config.file_dictionary_rewrite_threshold,
         }
@@ -537,6 +573,7 @@ impl DataKeyManager {
             Dicts::open(
                 &args.dict_path,
                 args.rotation_period,
+                args.rotation_file_threshold,
                 master_key,
                 args.enable_file_dictionary_log,
                 args.file_dictionary_rewrite_threshold,
@@ -554,6 +591,7 @@ impl DataKeyManager {
                 Ok(LoadDicts::Loaded(Dicts::new(
                     &args.dict_path,
                     args.rotation_period,
+                    args.rotation_file_threshold,
                     args.enable_file_dictionary_log,
                     args.file_dictionary_rewrite_threshold,
                 )?))
@@ -586,6 +624,7 @@ impl DataKeyManager {
         let dicts = Dicts::open(
             &args.dict_path,
             args.rotation_period,
+            args.rotation_file_threshold,
             previous_master_key,
             args.enable_file_dictionary_log,
             args.file_dictionary_rewrite_threshold,
@@ -617,11 +656,13 @@ impl DataKeyManager {
         dicts.maybe_rotate_data_key(method, &*master_key)?;
         let dicts = Arc::new(dicts);
         let dict_clone = dicts.clone();
+        let master_key = Arc::new(Mutex::new(master_key));
+        let master_key_clone = master_key.clone();
         let (rotate_tx, rx) = channel::bounded(1);
         let background_worker = std::thread::Builder::new()
             .name(thd_name!("enc:key"))
             .spawn_wrapper(move || {
-                run_background_rotate_work(dict_clone, method, &*master_key, rx);
+                run_background_rotate_work(dict_clone, method, master_key_clone, rx);
             })?;
 
         ENCRYPTION_INITIALIZED_GAUGE.set(1);
@@ -629,6 +670,7 @@ impl DataKeyManager {
         Ok(DataKeyManager {
             dicts,
             method,
+            master_key,
             rotate_tx,
             background_worker: Some(background_worker),
         })
@@ -921,6 +963,152 @@ impl DataKeyManager {
         Ok(())
     }
 
+    /// Encrypts a small known plaintext with the current data key and
+    /// immediately decrypts it again. An error means the key must not be
+    /// relied upon, e.g. a rotation should not be committed.
+    pub fn verify_key_roundtrip(&self) -> Result<()> {
+        const SAMPLE: &[u8] = b"tikv encryption key roundtrip";
+        if self.method == EncryptionMethod::Plaintext {
+            return Ok(());
+        }
+        let (key_id, data_key) = self.dicts.current_data_key();
+        let iv = Iv::new_ctr();
+        let mut encrypter = EncrypterWriter::new(Vec::new(), self.method, data_key.get_key(), iv)?;
+        encrypter.write_all(SAMPLE)?;
+        let ciphertext = encrypter.finalize()?;
+        let mut decrypter = DecrypterReader::new(
+            io::Cursor::new(ciphertext),
+            self.method,
+            data_key.get_key(),
+            iv,
+        )?;
+        let mut roundtrip = Vec::with_capacity(SAMPLE.len());
+        decrypter.read_to_end(&mut roundtrip)?;
+        if roundtrip != SAMPLE {
+            return Err(Error::Other(box_err!(
+                "data key {} failed the encrypt-decrypt roundtrip",
+                key_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Verifies the consistency of the encryption metadata:
+    /// - the current data key exists in the key dictionary and has a key of
+    ///   the length its method implies,
+    /// - every file in the file dictionary references a known data key,
+    /// - every encrypted file carries a well-formed IV.
+    ///
+    /// The dictionaries themselves are already authenticated on load (the key
+    /// dictionary by the master key's AEAD, the file dictionary by per-record
+    /// CRC32), so this catches cross-references broken by partial writes.
+    /// Returns the number of files checked.
+    pub fn fsck(&self) -> Result<usize> {
+        let mut issues = Vec::new();
+        let key_dict = self.dicts.key_dict.lock().unwrap();
+        let current_key_id = self.dicts.current_key_id.load(Ordering::SeqCst);
+        match key_dict.keys.get(&current_key_id) {
+            None => issues.push(format!("current data key {} is missing", current_key_id)),
+            Some(key) => {
+                let expected = crypter::get_method_key_length(key.method);
+                if key.key.len() != expected {
+                    issues.push(format!(
+                        "current data key {} has length {}, expect {}",
+                        current_key_id,
+                        key.key.len(),
+                        expected
+                    ));
+                }
+            }
+        }
+        let file_dict = self.dicts.file_dict.lock().unwrap();
+        for (fname, info) in file_dict.files.iter() {
+            if info.method == EncryptionMethod::Plaintext {
+                continue;
+            }
+            if !key_dict.keys.contains_key(&info.key_id) {
+                issues.push(format!(
+                    "file {} references unknown data key {}",
+                    fname, info.key_id
+                ));
+            }
+            if let Err(e) = Iv::from_slice(&info.iv) {
+                issues.push(format!("file {} has a malformed iv: {}", fname, e));
+            }
+        }
+        if !issues.is_empty() {
+            return Err(Error::Other(box_err!(
+                "encryption metadata fsck found {} issues: {}",
+                issues.len(),
+                issues.join("; ")
+            )));
+        }
+        Ok(file_dict.files.len())
+    }
+
+    /// Re-encrypts the key dictionary under `new_master_key` and routes all
+    /// further metadata writes to it, so the master key can be replaced
+    /// without restarting TiKV. Data keys and the file dictionary are left
+    /// untouched. On error the old master key stays in effect.
+    pub fn rotate_master_key(&self, new_master_key: Box<dyn Backend>) -> Result<()> {
+        if self.method != EncryptionMethod::Plaintext && !new_master_key.is_secure() {
+            return Err(box_err!(
+                "refuse to rotate master key to an absent or insecure backend"
+            ));
+        }
+        // Holding the lock also serializes with key dict saves issued by the
+        // background rotation worker.
+        let mut master_key = self.master_key.lock().unwrap();
+        self.dicts.save_key_dict(new_master_key.as_ref())?;
+        *master_key = new_master_key;
+        info!("encryption: rotated master key online.");
+        Ok(())
+    }
+
+    /// Rewrites the file at `path` under the current data key and method, so
+    /// that files written before a data key rotation no longer depend on the
+    /// rotated-away key. Files already on the current key, or not tracked by
+    /// the file dictionary, are left untouched. The content is streamed
+    /// through a temporary sibling file which then replaces the original.
+    pub fn reencrypt_file(&self, path: &Path) -> Result<()> {
+        let fname = path
+            .to_str()
+            .ok_or_else(|| Error::Other(box_err!("failed to convert path to string {:?}", path)))?;
+        let (current_key_id, _) = self.dicts.current_data_key();
+        match self.dicts.get_file(fname) {
+            Some(file) if file.key_id == current_key_id && file.method == self.method => {
+                // Already encrypted with the current key, nothing to rewrite.
+                return Ok(());
+            }
+            Some(_) => (),
+            // Not tracked by the file dictionary, i.e. a plaintext file
+            // created before encryption was enabled. Leave it as is.
+            None => return Ok(()),
+        }
+
+        let tmp_path = path.with_extension("reencrypt.tmp");
+        let tmp_fname = tmp_path
+            .to_str()
+            .ok_or_else(|| Error::Other(box_err!("invalid temporary path {:?}", tmp_path)))?;
+        let mut reader = self.open_file_for_read(path)?;
+        let mut writer = self.create_file_for_write(&tmp_path)?;
+        io::copy(&mut reader, &mut writer)?;
+        writer.finalize()?.sync_all()?;
+
+        // Point the original name at the new key before replacing the file, the
+        // same way `ImportPath::save` moves encrypted files around.
+        self.dicts.delete_file(fname, true)?;
+        self.link_file(tmp_fname, fname)?;
+        let r = file_system::rename(&tmp_path, path);
+        let del_file = if r.is_ok() { tmp_fname } else { fname };
+        if let Err(e) = self.delete_file(del_file, None) {
+            warn!("fail to remove encryption metadata during reencrypt_file";
+                "file" => del_file, "err" => ?e);
+        }
+        r?;
+        Ok(())
+    }
+
     pub fn link_file(&self, src_fname: &str, dst_fname: &str) -> IoResult<()> {
         let src_path = Path::new(src_fname);
         let dst_path = Path::new(dst_fname);
@@ -1161,6 +1349,7 @@ mod tests {
         DataKeyManagerArgs {
             method: EncryptionMethod::Aes256Ctr,
             rotation_period: Duration::from_secs(60),
+            rotation_file_threshold: 0,
             enable_file_dictionary_log: true,
             file_dictionary_rewrite_threshold: 2,
             dict_path: tmp_dir.path().to_str().unwrap().to_string(),
@@ -1317,6 +1506,59 @@ mod tests {
         assert_eq!(ENCRYPTION_FILE_NUM_GAUGE.get(), 1);
     }
 
+    #[test]
+    fn test_key_manager_rotate_master_key_online() {
+        let _guard = LOCK_FOR_GAUGE.lock().unwrap();
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let (key_path1, _tmp_key1) = create_key_file("key1");
+        // A second key file with different content.
+        let tmp_key2 = TempDir::new().unwrap();
+        let key_path2 = tmp_key2.path().join("key2");
+        let mut key_file2 = File::create(&key_path2).unwrap();
+        key_file2
+            .write_all(b"c3d99825f2181f4808acd2068eac7441a65bd428f14d2aab43fefc0129091139\n")
+            .unwrap();
+
+        let manager = new_key_manager(
+            &tmp_dir,
+            None,
+            Box::new(FileBackend::new(&key_path1).unwrap()),
+            Box::new(FileBackend::new(&key_path1).unwrap()),
+        )
+        .unwrap();
+        let info1 = manager.new_file("foo").unwrap();
+
+        // Rotating to an insecure backend is refused.
+        manager
+            .rotate_master_key(Box::<PlaintextBackend>::default())
+            .unwrap_err();
+
+        manager
+            .rotate_master_key(Box::new(FileBackend::new(&key_path2).unwrap()))
+            .unwrap();
+        drop(manager);
+
+        // The old master key can no longer open the dictionaries.
+        let manager = new_key_manager(
+            &tmp_dir,
+            None,
+            Box::new(FileBackend::new(&key_path1).unwrap()),
+            Box::new(FileBackend::new(&key_path1).unwrap()),
+        );
+        assert_matches!(manager.err(), Some(Error::BothMasterKeyFail(_, _)));
+
+        // The new master key alone suffices, no previous_master_key dance.
+        let manager = new_key_manager(
+            &tmp_dir,
+            None,
+            Box::new(FileBackend::new(&key_path2).unwrap()),
+            Box::new(FileBackend::new(&key_path2).unwrap()),
+        )
+        .unwrap();
+        let info2 = manager.get_file("foo").unwrap();
+        assert_eq!(info1, info2);
+    }
+
     #[test]
     fn test_key_manager_both_master_key_fail() {
         // create initial dictionaries.
@@ -1496,6 +1738,136 @@ mod tests {
         assert_ne!(current_key2, key);
     }
 
+    #[test]
+    fn test_key_manager_rotate_by_volume() {
+        let _guard = LOCK_FOR_GAUGE.lock().unwrap();
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let mut args = def_data_key_args(&tmp_dir);
+        args.rotation_file_threshold = 2;
+        let mut manager = match DataKeyManager::new_previous_loaded(
+            new_mock_backend(),
+            Box::<MockBackend>::default(),
+            args,
+        ) {
+            Ok(Some(dkm)) => dkm,
+            _ => panic!("expected encryption"),
+        };
+        let (key_id, _) = manager.dicts.current_data_key();
+        manager.shutdown_background_worker();
+        let master_key = MockBackend::default();
+
+        // Below the file threshold, the key stays.
+        manager.new_file("foo").unwrap();
+        manager
+            .dicts
+            .maybe_rotate_data_key(manager.method, &master_key)
+            .unwrap();
+        let (current_key_id, _) = manager.dicts.current_data_key();
+        assert_eq!(current_key_id, key_id);
+
+        // Crossing the threshold rotates the key even within rotation_period.
+        manager.new_file("bar").unwrap();
+        manager
+            .dicts
+            .maybe_rotate_data_key(manager.method, &master_key)
+            .unwrap();
+        let (current_key_id, _) = manager.dicts.current_data_key();
+        assert_ne!(current_key_id, key_id);
+        // The counter restarts for the new key.
+        assert_eq!(manager.dicts.current_key_files.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_key_manager_verify_key_roundtrip() {
+        let _guard = LOCK_FOR_GAUGE.lock().unwrap();
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let manager = new_key_manager_def(&tmp_dir, None).unwrap();
+        manager.verify_key_roundtrip().unwrap();
+
+        // Corrupt the current data key to emulate a backend handing back a
+        // key that cannot actually be used.
+        {
+            let mut key_dict = manager.dicts.key_dict.lock().unwrap();
+            let current_key_id = key_dict.current_key_id;
+            key_dict
+                .keys
+                .get_mut(&current_key_id)
+                .unwrap()
+                .set_key(vec![0; 8]);
+        }
+        manager.verify_key_roundtrip().unwrap_err();
+    }
+
+    #[test]
+    fn test_key_manager_fsck() {
+        let _guard = LOCK_FOR_GAUGE.lock().unwrap();
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let manager = new_key_manager_def(&tmp_dir, None).unwrap();
+        manager.new_file("foo").unwrap();
+        manager.new_file("bar").unwrap();
+        assert_eq!(manager.fsck().unwrap(), 2);
+
+        // A dangling key reference must be reported.
+        {
+            let mut file_dict = manager.dicts.file_dict.lock().unwrap();
+            file_dict.files.get_mut("foo").unwrap().key_id = 0;
+        }
+        let err = manager.fsck().unwrap_err();
+        assert!(format!("{}", err).contains("unknown data key"));
+    }
+
+    #[test]
+    fn test_key_manager_reencrypt_file() {
+        use std::io::{Read as _, Write as _};
+
+        let _guard = LOCK_FOR_GAUGE.lock().unwrap();
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let file_dir = tempfile::TempDir::new().unwrap();
+        let mut manager = new_key_manager_def(&tmp_dir, None).unwrap();
+        manager.shutdown_background_worker();
+
+        let path = file_dir.path().join("foo");
+        let content = b"reencrypt me please";
+        let mut writer = manager.create_file_for_write(&path).unwrap();
+        writer.write_all(content).unwrap();
+        writer.finalize().unwrap().sync_all().unwrap();
+        let old_key_id = manager
+            .dicts
+            .get_file(path.to_str().unwrap())
+            .unwrap()
+            .key_id;
+
+        // Rotate the current data key away.
+        unsafe {
+            let ptr: *mut Dicts = manager.dicts.as_ref() as *const Dicts as *mut Dicts;
+            let mut dict = Box::from_raw(ptr);
+            dict.rotation_period = Duration::from_millis(1);
+            Box::leak(dict);
+        }
+        std::thread::sleep(Duration::from_secs(1));
+        let master_key = MockBackend::default();
+        manager
+            .dicts
+            .maybe_rotate_data_key(manager.method, &master_key)
+            .unwrap();
+        let (current_key_id, _) = manager.dicts.current_data_key();
+        assert_ne!(current_key_id, old_key_id);
+
+        manager.reencrypt_file(&path).unwrap();
+        // The file now references the current key and is still readable.
+        let file = manager.dicts.get_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(file.key_id, current_key_id);
+        let mut reader = manager.open_file_for_read(&path).unwrap();
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, content);
+
+        // Re-encrypting a file already on the current key is a no-op.
+        manager.reencrypt_file(&path).unwrap();
+        let file = manager.dicts.get_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(file.key_id, current_key_id);
+    }
+
     #[test]
     fn test_key_manager_persistence() {
         let _guard = LOCK_FOR_GAUGE.lock().unwrap();
@@ -26,4 +26,12 @@ lazy_static::lazy_static! {
         "Total size of ecryption meta files",
         &["name"]
     ).unwrap();
+    pub static ref ENCRYPTION_DATA_KEY_AGE_GAUGE: IntGauge = register_int_gauge!(
+        "tikv_encryption_data_key_age_seconds",
+        "Age of the current encryption data key"
+    ).unwrap();
+    pub static ref ENCRYPTION_DATA_KEY_FILE_NUM_GAUGE: IntGauge = register_int_gauge!(
+        "tikv_encryption_data_key_file_num",
+        "Number of files encrypted with the current data key"
+    ).unwrap();
 }
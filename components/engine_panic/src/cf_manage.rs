@@ -0,0 +1,15 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use engine_traits::{CfManageExt, Result};
+
+use crate::{cf_options::PanicCfOptions, engine::PanicEngine};
+
+impl CfManageExt for PanicEngine {
+    fn create_cf(&mut self, name: &str, opts: PanicCfOptions) -> Result<()> {
+        panic!()
+    }
+
+    fn drop_cf(&mut self, name: &str) -> Result<()> {
+        panic!()
+    }
+}
@@ -13,6 +13,10 @@ impl CompactExt for PanicEngine {
         panic!()
     }
 
+    fn compact_all(&self, exclusive_manual: bool) -> Result<()> {
+        panic!()
+    }
+
     fn compact_range_cf(
         &self,
         cf: &str,
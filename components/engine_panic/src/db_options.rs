@@ -28,6 +28,10 @@ impl DbOptions for PanicDbOptions {
         panic!()
     }
 
+    fn get_wal_bytes_per_sync(&self) -> u64 {
+        panic!()
+    }
+
     fn get_rate_bytes_per_sec(&self) -> Option<i64> {
         panic!()
     }
@@ -12,6 +12,15 @@ impl ImportExt for PanicEngine {
     fn ingest_external_file_cf(&self, cf: &str, files: &[&str]) -> Result<()> {
         panic!()
     }
+
+    fn ingest_external_file_cf_opt(
+        &self,
+        cf: &str,
+        files: &[&str],
+        opts: &Self::IngestExternalFileOptions,
+    ) -> Result<()> {
+        panic!()
+    }
 }
 
 pub struct PanicIngestExternalFileOptions;
@@ -32,4 +41,12 @@ impl IngestExternalFileOptions for PanicIngestExternalFileOptions {
     fn set_write_global_seqno(&mut self, f: bool) {
         panic!()
     }
+
+    fn get_allow_write(&self) -> bool {
+        panic!()
+    }
+
+    fn set_allow_write(&mut self, f: bool) {
+        panic!()
+    }
 }
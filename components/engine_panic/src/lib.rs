@@ -11,6 +11,8 @@
 
 #![allow(unused)]
 
+mod cf_manage;
+pub use crate::cf_manage::*;
 mod cf_names;
 pub use crate::cf_names::*;
 mod cf_options;
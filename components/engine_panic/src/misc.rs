@@ -1,7 +1,8 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 use engine_traits::{
-    DeleteStrategy, MiscExt, Range, RangeStats, Result, StatisticsReporter, WriteOptions,
+    DeleteReport, DeleteStrategy, MiscExt, Range, RangeStats, Result, SstFileMeta,
+    StatisticsReporter, WriteOptions,
 };
 
 use crate::engine::PanicEngine;
@@ -41,13 +42,13 @@ impl MiscExt for PanicEngine {
         panic!()
     }
 
-    fn delete_ranges_cf(
+    fn delete_ranges_cf_report(
         &self,
         wopts: &WriteOptions,
         cf: &str,
         strategy: DeleteStrategy,
         ranges: &[Range<'_>],
-    ) -> Result<bool> {
+    ) -> Result<DeleteReport> {
         panic!()
     }
 
@@ -55,6 +56,10 @@ impl MiscExt for PanicEngine {
         panic!()
     }
 
+    fn warmup_cf_range(&self, cf: &str, range: &Range<'_>) -> Result<u64> {
+        panic!()
+    }
+
     fn ingest_maybe_slowdown_writes(&self, cf: &str) -> Result<bool> {
         panic!()
     }
@@ -63,6 +68,10 @@ impl MiscExt for PanicEngine {
         panic!()
     }
 
+    fn get_sst_files_cf(&self, cf: &str, range: &Range<'_>) -> Result<Vec<SstFileMeta>> {
+        panic!()
+    }
+
     fn get_engine_used_size(&self) -> Result<u64> {
         panic!()
     }
@@ -103,10 +112,26 @@ impl MiscExt for PanicEngine {
         panic!()
     }
 
+    fn get_oldest_snapshot_age(&self) -> Result<Option<std::time::Duration>> {
+        panic!()
+    }
+
     fn get_total_sst_files_size_cf(&self, cf: &str) -> Result<Option<u64>> {
         panic!()
     }
 
+    fn get_block_cache_capacity(&self) -> Result<u64> {
+        panic!()
+    }
+
+    fn set_block_cache_capacity(&self, capacity: u64) -> Result<()> {
+        panic!()
+    }
+
+    fn get_block_cache_usage(&self) -> Result<u64> {
+        panic!()
+    }
+
     fn get_num_keys(&self) -> Result<u64> {
         panic!()
     }
@@ -49,6 +49,9 @@ impl WriteBatch for PanicWriteBatch {
     fn rollback_to_save_point(&mut self) -> Result<()> {
         panic!()
     }
+    fn save_point_depth(&self) -> usize {
+        panic!()
+    }
     fn merge(&mut self, src: Self) -> Result<()> {
         panic!()
     }
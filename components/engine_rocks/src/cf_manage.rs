@@ -0,0 +1,60 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use engine_traits::{CfManageExt, Result};
+
+use crate::{r2e, RocksCfOptions, RocksEngine};
+
+impl CfManageExt for RocksEngine {
+    fn create_cf(&mut self, name: &str, opts: RocksCfOptions) -> Result<()> {
+        let db = self.as_inner_mut().ok_or_else(|| {
+            r2e("cannot create column family while other handles to the engine are alive")
+        })?;
+        db.create_cf((name, opts.into_raw())).map_err(r2e)?;
+        Ok(())
+    }
+
+    fn drop_cf(&mut self, name: &str) -> Result<()> {
+        let db = self.as_inner_mut().ok_or_else(|| {
+            r2e("cannot drop column family while other handles to the engine are alive")
+        })?;
+        db.drop_cf(name).map_err(r2e)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine_traits::{CfManageExt, CfNamesExt, Peekable, SyncMutable, CF_DEFAULT};
+    use tempfile::Builder;
+
+    use crate::{util::new_engine, RocksCfOptions};
+
+    #[test]
+    fn test_create_and_drop_cf() {
+        let path = Builder::new()
+            .prefix("test-create-and-drop-cf")
+            .tempdir()
+            .unwrap();
+        let mut engine = new_engine(path.path().to_str().unwrap(), &[CF_DEFAULT]).unwrap();
+
+        engine
+            .create_cf("new_cf", RocksCfOptions::default())
+            .unwrap();
+        assert!(engine.cf_names().contains(&"new_cf"));
+        engine.put_cf("new_cf", b"k", b"v").unwrap();
+        assert_eq!(engine.get_value_cf("new_cf", b"k").unwrap().unwrap(), b"v");
+
+        engine.drop_cf("new_cf").unwrap();
+        assert!(!engine.cf_names().contains(&"new_cf"));
+
+        // Managing column families requires the sole handle to the engine.
+        let clone = engine.clone();
+        engine
+            .create_cf("another_cf", RocksCfOptions::default())
+            .unwrap_err();
+        drop(clone);
+        engine
+            .create_cf("another_cf", RocksCfOptions::default())
+            .unwrap();
+    }
+}
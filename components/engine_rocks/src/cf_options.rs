@@ -148,3 +148,65 @@ impl CfOptions for RocksCfOptions {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use engine_traits::{CfOptionsExt, MiscExt, Peekable, SyncMutable, CF_DEFAULT, CF_LOCK};
+    use rocksdb::DBCompactionStyle;
+    use tempfile::Builder;
+
+    use crate::{util::new_engine_opt, RocksCfOptions, RocksDbOptions};
+
+    #[test]
+    fn test_per_cf_compaction_style() {
+        let path = Builder::new()
+            .prefix("test_per_cf_compaction_style")
+            .tempdir()
+            .unwrap();
+        // Lock CF sees frequent overwrites of a small key space, which suits
+        // universal compaction, while the default CF stays on level style.
+        let mut lock_cf_opts = RocksCfOptions::default();
+        lock_cf_opts.set_compaction_style(DBCompactionStyle::Universal);
+        let mut default_cf_opts = RocksCfOptions::default();
+        default_cf_opts.set_compaction_style(DBCompactionStyle::Level);
+        let engine = new_engine_opt(
+            path.path().to_str().unwrap(),
+            RocksDbOptions::default(),
+            vec![(CF_DEFAULT, default_cf_opts), (CF_LOCK, lock_cf_opts)],
+        )
+        .unwrap();
+
+        for cf in [CF_DEFAULT, CF_LOCK] {
+            for i in 0..10u8 {
+                engine.put_cf(cf, &[i], &[i]).unwrap();
+            }
+            engine.flush_cf(cf, true).unwrap();
+            assert_eq!(engine.get_value_cf(cf, &[5]).unwrap().unwrap(), &[5]);
+        }
+    }
+
+    #[test]
+    fn test_cf_options_fingerprint() {
+        let new_engine = |prefix: &str, write_buffer_number: i32| {
+            let path = Builder::new().prefix(prefix).tempdir().unwrap();
+            let mut cf_opts = RocksCfOptions::default();
+            cf_opts.set_max_write_buffer_number(write_buffer_number);
+            let engine = new_engine_opt(
+                path.path().to_str().unwrap(),
+                RocksDbOptions::default(),
+                vec![(CF_DEFAULT, cf_opts)],
+            )
+            .unwrap();
+            (path, engine)
+        };
+
+        let (_d1, e1) = new_engine("fingerprint-a", 4);
+        let (_d2, e2) = new_engine("fingerprint-b", 4);
+        let (_d3, e3) = new_engine("fingerprint-c", 8);
+        let f1 = e1.cf_options_fingerprint(CF_DEFAULT).unwrap();
+        let f2 = e2.cf_options_fingerprint(CF_DEFAULT).unwrap();
+        let f3 = e3.cf_options_fingerprint(CF_DEFAULT).unwrap();
+        assert_eq!(f1, f2);
+        assert_ne!(f1, f3);
+    }
+}
@@ -68,4 +68,17 @@ mod tests {
         let engine2 = new_engine(path2.as_path().to_str().unwrap(), ALL_CFS).unwrap();
         assert_eq!(engine2.get_value(b"key").unwrap().unwrap(), b"value");
     }
+
+    #[test]
+    fn test_create_checkpoint() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("origin");
+        let engine = new_engine(path.as_path().to_str().unwrap(), ALL_CFS).unwrap();
+        engine.put(b"key", b"value").unwrap();
+
+        let path2 = dir.path().join("checkpoint");
+        engine.create_checkpoint(path2.as_path()).unwrap();
+        let engine2 = new_engine(path2.as_path().to_str().unwrap(), ALL_CFS).unwrap();
+        assert_eq!(engine2.get_value(b"key").unwrap().unwrap(), b"value");
+    }
 }
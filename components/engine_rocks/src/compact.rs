@@ -3,7 +3,9 @@
 use std::cmp;
 
 use engine_traits::{CfNamesExt, CompactExt, Result};
-use rocksdb::{CompactOptions, CompactionOptions, DBCompressionType};
+use rocksdb::{
+    CompactOptions, CompactionOptions, DBBottommostLevelCompaction, DBCompressionType,
+};
 
 use crate::{engine::RocksEngine, r2e, util};
 
@@ -24,6 +26,20 @@ impl CompactExt for RocksEngine {
         Ok(false)
     }
 
+    fn compact_all(&self, exclusive_manual: bool) -> Result<()> {
+        let db = self.as_inner();
+        for cf in self.cf_names() {
+            let handle = util::get_cf_handle(db, cf)?;
+            let mut compact_opts = CompactOptions::new();
+            compact_opts.set_exclusive_manual_compaction(exclusive_manual);
+            // Also rewrite files already sitting at the bottommost level, so
+            // the whole key space ends up freshly compacted.
+            compact_opts.set_bottommost_level_compaction(DBBottommostLevelCompaction::Force);
+            db.compact_range_cf_opt(handle, &compact_opts, None, None);
+        }
+        Ok(())
+    }
+
     fn compact_range_cf(
         &self,
         cf: &str,
@@ -209,4 +225,86 @@ mod tests {
             assert_eq!(level_n[0].get_largestkey(), &[4]);
         }
     }
+
+    #[test]
+    fn test_compact_range_cf() {
+        let temp_dir = Builder::new()
+            .prefix("test_compact_range_cf")
+            .tempdir()
+            .unwrap();
+
+        let mut cf_opts = RocksCfOptions::default();
+        cf_opts.set_disable_auto_compactions(true);
+        let cfs_opts = vec![("default", cf_opts.clone()), ("test", cf_opts)];
+        let db = util::new_engine_opt(
+            temp_dir.path().to_str().unwrap(),
+            RocksDbOptions::default(),
+            cfs_opts,
+        )
+        .unwrap();
+
+        for cf_name in db.cf_names() {
+            for i in 0..5 {
+                db.put_cf(cf_name, &[i], &[i]).unwrap();
+                db.flush_cf(cf_name, true).unwrap();
+            }
+        }
+
+        // Only the requested column family is compacted.
+        db.compact_range_cf("default", Some(&[0]), Some(&[6]), false, 1)
+            .unwrap();
+
+        let cf = util::get_cf_handle(db.as_inner(), "default").unwrap();
+        assert_eq!(
+            util::get_cf_num_files_at_level(db.as_inner(), cf, 0).unwrap(),
+            0
+        );
+        let cf_opts = db.get_options_cf("default").unwrap();
+        let bottommost = cf_opts.get_num_levels() - 1;
+        assert!(util::get_cf_num_files_at_level(db.as_inner(), cf, bottommost).unwrap() > 0);
+
+        let cf = util::get_cf_handle(db.as_inner(), "test").unwrap();
+        assert_eq!(
+            util::get_cf_num_files_at_level(db.as_inner(), cf, 0).unwrap(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_compact_all() {
+        let temp_dir = Builder::new().prefix("test_compact_all").tempdir().unwrap();
+
+        let mut cf_opts = RocksCfOptions::default();
+        cf_opts.set_disable_auto_compactions(true);
+        let cfs_opts = vec![("default", cf_opts.clone()), ("test", cf_opts)];
+        let db = util::new_engine_opt(
+            temp_dir.path().to_str().unwrap(),
+            RocksDbOptions::default(),
+            cfs_opts,
+        )
+        .unwrap();
+
+        for cf_name in db.cf_names() {
+            for i in 0..5 {
+                db.put_cf(cf_name, &[i], &[i]).unwrap();
+                db.flush_cf(cf_name, true).unwrap();
+            }
+        }
+
+        db.compact_all(false).unwrap();
+
+        // All data ends up at the bottommost level afterwards.
+        for cf_name in db.cf_names() {
+            let cf = util::get_cf_handle(db.as_inner(), cf_name).unwrap();
+            let cf_opts = db.get_options_cf(cf_name).unwrap();
+            let bottommost = cf_opts.get_num_levels() - 1;
+            for level in 0..bottommost {
+                assert_eq!(
+                    util::get_cf_num_files_at_level(db.as_inner(), cf, level).unwrap(),
+                    0
+                );
+            }
+            assert!(util::get_cf_num_files_at_level(db.as_inner(), cf, bottommost).unwrap() > 0);
+        }
+    }
 }
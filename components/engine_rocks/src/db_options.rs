@@ -65,6 +65,10 @@ impl DbOptions for RocksDbOptions {
         self.0.get_max_background_jobs()
     }
 
+    fn get_wal_bytes_per_sync(&self) -> u64 {
+        self.0.get_wal_bytes_per_sync()
+    }
+
     fn get_rate_bytes_per_sec(&self) -> Option<i64> {
         self.0.get_rate_limiter().map(|r| r.get_bytes_per_second())
     }
@@ -159,3 +163,25 @@ impl TitanCfOptions for RocksTitanDbOptions {
         self.0.set_min_blob_size(size)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use engine_traits::{DbOptions, DbOptionsExt, CF_DEFAULT};
+    use tempfile::Builder;
+
+    use crate::util;
+
+    #[test]
+    fn test_wal_bytes_per_sync() {
+        let path = Builder::new()
+            .prefix("test-wal-bytes-per-sync")
+            .tempdir()
+            .unwrap();
+        let engine = util::new_engine(path.path().to_str().unwrap(), &[CF_DEFAULT]).unwrap();
+
+        engine.set_wal_bytes_per_sync(512 * 1024).unwrap();
+        assert_eq!(engine.get_db_options().get_wal_bytes_per_sync(), 512 * 1024);
+        engine.set_wal_bytes_per_sync(0).unwrap();
+        assert_eq!(engine.get_db_options().get_wal_bytes_per_sync(), 0);
+    }
+}
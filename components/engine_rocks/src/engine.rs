@@ -165,6 +165,12 @@ impl RocksEngine {
         &self.db
     }
 
+    /// Mutable access to the underlying db. Only available while this is the
+    /// sole handle to the engine, i.e. no clones or snapshots are alive.
+    pub(crate) fn as_inner_mut(&mut self) -> Option<&mut DB> {
+        Arc::get_mut(&mut self.db)
+    }
+
     pub fn get_sync_db(&self) -> Arc<DB> {
         self.db.clone()
     }
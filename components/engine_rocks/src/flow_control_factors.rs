@@ -29,4 +29,52 @@ impl FlowControlFactorsExt for RocksEngine {
             handle,
         ))
     }
+
+    fn get_cf_is_write_stalled(&self, cf: &str) -> Result<Option<bool>> {
+        const ROCKSDB_IS_WRITE_STALLED: &str = "rocksdb.is-write-stalled";
+        const ROCKSDB_IS_WRITE_STOPPED: &str = "rocksdb.is-write-stopped";
+        let handle = util::get_cf_handle(self.as_inner(), cf)?;
+        let stalled = self
+            .as_inner()
+            .get_property_int_cf(handle, ROCKSDB_IS_WRITE_STALLED);
+        let stopped = self
+            .as_inner()
+            .get_property_int_cf(handle, ROCKSDB_IS_WRITE_STOPPED);
+        if stalled.is_none() && stopped.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(
+            stalled.unwrap_or_default() != 0 || stopped.unwrap_or_default() != 0,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use engine_traits::{SyncMutable, CF_DEFAULT};
+    use tempfile::Builder;
+
+    use super::*;
+    use crate::util::new_engine;
+
+    #[test]
+    fn test_flow_control_factors() {
+        let path = Builder::new()
+            .prefix("test_flow_control_factors")
+            .tempdir()
+            .unwrap();
+        let db = new_engine(path.path().to_str().unwrap(), &[CF_DEFAULT]).unwrap();
+        db.put(b"k1", b"v1").unwrap();
+
+        assert_eq!(
+            db.get_cf_num_immutable_mem_table(CF_DEFAULT).unwrap(),
+            Some(0)
+        );
+        assert_eq!(
+            db.get_cf_pending_compaction_bytes(CF_DEFAULT).unwrap(),
+            Some(0)
+        );
+        // A freshly opened engine is not stalled.
+        assert_eq!(db.get_cf_is_write_stalled(CF_DEFAULT).unwrap(), Some(false));
+    }
 }
@@ -13,58 +13,90 @@ impl ImportExt for RocksEngine {
     type IngestExternalFileOptions = RocksIngestExternalFileOptions;
 
     fn ingest_external_file_cf(&self, cf: &str, files: &[&str]) -> Result<()> {
-        let cf = util::get_cf_handle(self.as_inner(), cf)?;
         let mut opts = RocksIngestExternalFileOptions::new();
         opts.move_files(true);
         opts.set_write_global_seqno(false);
-        files.iter().try_for_each(|file| -> Result<()> {
-            let f = File::open(file)?;
-            // Prior to v5.2.0, TiKV use `write_global_seqno=true` for ingestion. For
-            // backward compatibility, in case TiKV is retrying an ingestion job
-            // generated by older version, it needs to reset the global seqno to
-            // 0.
-            set_external_sst_file_global_seq_no(self.as_inner(), cf, file, 0).map_err(r2e)?;
-            f.sync_all()
-                .map_err(|e| format!("sync {}: {:?}", file, e))
-                .map_err(r2e)
-        })?;
-        // This is calling a specially optimized version of
-        // ingest_external_file_cf. In cases where the memtable needs to be
-        // flushed it avoids blocking writers while doing the flush. The unused
-        // return value here just indicates whether the fallback path requiring
-        // the manual memtable flush was taken.
-        let _did_nonblocking_memtable_flush = self
-            .as_inner()
-            .ingest_external_file_optimized(cf, &opts.0, files)
-            .map_err(r2e)?;
+        opts.set_allow_write(true);
+        self.ingest_external_file_cf_opt(cf, files, &opts)
+    }
+
+    fn ingest_external_file_cf_opt(
+        &self,
+        cf: &str,
+        files: &[&str],
+        opts: &RocksIngestExternalFileOptions,
+    ) -> Result<()> {
+        let cf = util::get_cf_handle(self.as_inner(), cf)?;
+        if !opts.get_write_global_seqno() {
+            files.iter().try_for_each(|file| -> Result<()> {
+                let f = File::open(file)?;
+                // Prior to v5.2.0, TiKV use `write_global_seqno=true` for ingestion. For
+                // backward compatibility, in case TiKV is retrying an ingestion job
+                // generated by older version, it needs to reset the global seqno to
+                // 0.
+                set_external_sst_file_global_seq_no(self.as_inner(), cf, file, 0).map_err(r2e)?;
+                f.sync_all()
+                    .map_err(|e| format!("sync {}: {:?}", file, e))
+                    .map_err(r2e)
+            })?;
+        }
+        if opts.get_allow_write() {
+            // This is calling a specially optimized version of
+            // ingest_external_file_cf. In cases where the memtable needs to be
+            // flushed it avoids blocking writers while doing the flush. The unused
+            // return value here just indicates whether the fallback path requiring
+            // the manual memtable flush was taken.
+            let _did_nonblocking_memtable_flush = self
+                .as_inner()
+                .ingest_external_file_optimized(cf, &opts.raw, files)
+                .map_err(r2e)?;
+        } else {
+            self.as_inner()
+                .ingest_external_file_cf(cf, &opts.raw, files)
+                .map_err(r2e)?;
+        }
         Ok(())
     }
 }
 
-pub struct RocksIngestExternalFileOptions(RawIngestExternalFileOptions);
+pub struct RocksIngestExternalFileOptions {
+    raw: RawIngestExternalFileOptions,
+    allow_write: bool,
+}
 
 impl IngestExternalFileOptions for RocksIngestExternalFileOptions {
     fn new() -> RocksIngestExternalFileOptions {
-        RocksIngestExternalFileOptions(RawIngestExternalFileOptions::new())
+        RocksIngestExternalFileOptions {
+            raw: RawIngestExternalFileOptions::new(),
+            allow_write: false,
+        }
     }
 
     fn move_files(&mut self, f: bool) {
-        self.0.move_files(f);
+        self.raw.move_files(f);
     }
 
     fn get_write_global_seqno(&self) -> bool {
-        self.0.get_write_global_seqno()
+        self.raw.get_write_global_seqno()
     }
 
     fn set_write_global_seqno(&mut self, f: bool) {
-        self.0.set_write_global_seqno(f);
+        self.raw.set_write_global_seqno(f);
+    }
+
+    fn get_allow_write(&self) -> bool {
+        self.allow_write
+    }
+
+    fn set_allow_write(&mut self, f: bool) {
+        self.allow_write = f;
     }
 }
 
 #[cfg(test)]
 mod tests {
     use engine_traits::{
-        FlowControlFactorsExt, MiscExt, Mutable, SstWriter, SstWriterBuilder, WriteBatch,
+        FlowControlFactorsExt, MiscExt, Mutable, Peekable, SstWriter, SstWriterBuilder, WriteBatch,
         WriteBatchExt, ALL_CFS, CF_DEFAULT,
     };
     use tempfile::Builder;
@@ -135,4 +167,45 @@ mod tests {
         db.ingest_external_file_cf(CF_DEFAULT, &[p1.to_str().unwrap(), p2.to_str().unwrap()])
             .unwrap();
     }
+
+    #[test]
+    fn test_ingest_external_file_cf_opt() {
+        let path_dir = Builder::new()
+            .prefix("test_ingest_external_file_cf_opt")
+            .tempdir()
+            .unwrap();
+        let root_path = path_dir.path();
+        let db_path = root_path.join("db");
+        let db = new_engine_opt(
+            db_path.to_str().unwrap(),
+            RocksDbOptions::default(),
+            vec![(CF_DEFAULT, RocksCfOptions::default())],
+        )
+        .unwrap();
+
+        let p = root_path.join("sst");
+        let mut sst = RocksSstWriterBuilder::new()
+            .set_db(&db)
+            .set_cf(CF_DEFAULT)
+            .build(p.to_str().unwrap())
+            .unwrap();
+        for i in 1000..2000 {
+            let v = i.to_string();
+            sst.put(v.as_bytes(), v.as_bytes()).unwrap();
+        }
+        sst.finish().unwrap();
+
+        // Ingest through the blocking path without mutating the SST.
+        let mut opts = RocksIngestExternalFileOptions::new();
+        opts.move_files(true);
+        opts.set_write_global_seqno(false);
+        assert!(!opts.get_allow_write());
+        db.ingest_external_file_cf_opt(CF_DEFAULT, &[p.to_str().unwrap()], &opts)
+            .unwrap();
+        assert!(
+            db.get_value_cf(CF_DEFAULT, b"1500")
+                .unwrap()
+                .is_some()
+        );
+    }
 }
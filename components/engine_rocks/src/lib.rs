@@ -26,6 +26,8 @@ extern crate tikv_alloc;
 #[cfg(test)]
 extern crate test;
 
+mod cf_manage;
+pub use crate::cf_manage::*;
 mod cf_names;
 pub use crate::cf_names::*;
 mod cf_options;
@@ -1,12 +1,12 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 use engine_traits::{
-    CfNamesExt, DeleteStrategy, ImportExt, IterOptions, Iterable, Iterator, MiscExt, Mutable,
-    Range, RangeStats, Result, SstWriter, SstWriterBuilder, WriteBatch, WriteBatchExt,
-    WriteOptions,
+    AppliedDeleteStrategy, CfNamesExt, DeleteReport, DeleteStrategy, ImportExt, IterOptions,
+    Iterable, Iterator, MiscExt, Mutable, Range, RangeStats, Result, SstFileMeta, SstWriter,
+    SstWriterBuilder, WriteBatch, WriteBatchExt, WriteOptions, CF_DEFAULT,
 };
 use rocksdb::{FlushOptions, Range as RocksRange};
-use tikv_util::{box_try, keybuilder::KeyBuilder};
+use tikv_util::{box_err, box_try, keybuilder::KeyBuilder};
 
 use crate::{
     engine::RocksEngine,
@@ -26,14 +26,18 @@ impl RocksEngine {
 
     // We store all data which would be deleted in memory at first because the data
     // of region will never be larger than max-region-size.
+    //
+    // Also returns which concrete strategy handled each range, in the sorted
+    // processing order.
     fn delete_all_in_range_cf_by_ingest(
         &self,
         wopts: &WriteOptions,
         cf: &str,
         sst_path: String,
         ranges: &[Range<'_>],
-    ) -> Result<bool> {
+    ) -> Result<(bool, Vec<AppliedDeleteStrategy>)> {
         let mut written = false;
+        let mut applied = Vec::with_capacity(ranges.len());
         let mut ranges = ranges.to_owned();
         ranges.sort_by(|a, b| a.start_key.cmp(b.start_key));
 
@@ -47,9 +51,11 @@ impl RocksEngine {
                 .map_or(false, |key| key.as_slice() > r.start_key)
             {
                 written |= self.delete_all_in_range_cf_by_key(wopts, cf, &r)?;
+                applied.push(AppliedDeleteStrategy::DeleteByKey);
                 continue;
             }
             last_end_key = Some(r.end_key.to_owned());
+            applied.push(AppliedDeleteStrategy::DeleteByWriter);
 
             let mut opts = IterOptions::new(
                 Some(KeyBuilder::from_slice(r.start_key, 0, 0)),
@@ -102,7 +108,7 @@ impl RocksEngine {
                 written = true;
             }
         }
-        Ok(written)
+        Ok((written, applied))
     }
 
     fn delete_all_in_range_cf_by_key(
@@ -204,16 +210,16 @@ impl MiscExt for RocksEngine {
         Ok(false)
     }
 
-    fn delete_ranges_cf(
+    fn delete_ranges_cf_report(
         &self,
         wopts: &WriteOptions,
         cf: &str,
         strategy: DeleteStrategy,
         ranges: &[Range<'_>],
-    ) -> Result<bool> {
-        let mut written = false;
+    ) -> Result<DeleteReport> {
+        let mut report = DeleteReport::default();
         if ranges.is_empty() {
-            return Ok(written);
+            return Ok(report);
         }
         match strategy {
             DeleteStrategy::DeleteFiles => {
@@ -229,8 +235,9 @@ impl MiscExt for RocksEngine {
                     })
                     .collect();
                 if rocks_ranges.is_empty() {
-                    return Ok(written);
+                    return Ok(report);
                 }
+                report.applied = vec![AppliedDeleteStrategy::DeleteFiles; rocks_ranges.len()];
                 self.as_inner()
                     .delete_files_in_ranges_cf(handle, &rocks_ranges, false)
                     .map_err(r2e)?;
@@ -249,8 +256,9 @@ impl MiscExt for RocksEngine {
                         })
                         .collect();
                     if rocks_ranges.is_empty() {
-                        return Ok(written);
+                        return Ok(report);
                     }
+                    report.applied = vec![AppliedDeleteStrategy::DeleteBlobs; rocks_ranges.len()];
                     self.as_inner()
                         .delete_blob_files_in_ranges_cf(handle, &rocks_ranges, false)
                         .map_err(r2e)?;
@@ -260,20 +268,62 @@ impl MiscExt for RocksEngine {
                 let mut wb = self.write_batch();
                 for r in ranges.iter() {
                     wb.delete_range_cf(cf, r.start_key, r.end_key)?;
+                    report.applied.push(AppliedDeleteStrategy::DeleteByRange);
                 }
                 wb.write_opt(wopts)?;
-                written = true;
+                report.written = true;
             }
             DeleteStrategy::DeleteByKey => {
                 for r in ranges {
-                    written |= self.delete_all_in_range_cf_by_key(wopts, cf, r)?;
+                    report.written |= self.delete_all_in_range_cf_by_key(wopts, cf, r)?;
+                    report.applied.push(AppliedDeleteStrategy::DeleteByKey);
                 }
             }
             DeleteStrategy::DeleteByWriter { sst_path } => {
-                written |= self.delete_all_in_range_cf_by_ingest(wopts, cf, sst_path, ranges)?;
+                let (written, applied) =
+                    self.delete_all_in_range_cf_by_ingest(wopts, cf, sst_path, ranges)?;
+                report.written |= written;
+                report.applied = applied;
+            }
+            DeleteStrategy::Auto => {
+                let mut big_ranges = Vec::new();
+                for r in ranges {
+                    if r.start_key >= r.end_key {
+                        continue;
+                    }
+                    let (mem_keys, _) = self.get_approximate_memtable_stats_cf(cf, r)?;
+                    let sst_keys = self
+                        .get_range_stats(cf, r.start_key, r.end_key)?
+                        .map_or(0, |s| s.num_entries);
+                    if mem_keys + sst_keys <= MAX_DELETE_COUNT_BY_KEY as u64 {
+                        report.written |= self.delete_all_in_range_cf_by_key(wopts, cf, r)?;
+                        report.applied.push(AppliedDeleteStrategy::DeleteByKey);
+                    } else {
+                        big_ranges.push(*r);
+                    }
+                }
+                if !big_ranges.is_empty() {
+                    // Drop fully covered SST files first so the range
+                    // tombstones below cover less data.
+                    let handle = util::get_cf_handle(self.as_inner(), cf)?;
+                    let rocks_ranges: Vec<_> = big_ranges
+                        .iter()
+                        .map(|r| RocksRange::new(r.start_key, r.end_key))
+                        .collect();
+                    self.as_inner()
+                        .delete_files_in_ranges_cf(handle, &rocks_ranges, false)
+                        .map_err(r2e)?;
+                    let mut wb = self.write_batch();
+                    for r in big_ranges {
+                        wb.delete_range_cf(cf, r.start_key, r.end_key)?;
+                        report.applied.push(AppliedDeleteStrategy::DeleteByRange);
+                    }
+                    wb.write_opt(wopts)?;
+                    report.written = true;
+                }
             }
         }
-        Ok(written)
+        Ok(report)
     }
 
     fn get_approximate_memtable_stats_cf(&self, cf: &str, range: &Range<'_>) -> Result<(u64, u64)> {
@@ -284,6 +334,20 @@ impl MiscExt for RocksEngine {
             .get_approximate_memtable_stats_cf(handle, &range))
     }
 
+    fn warmup_cf_range(&self, cf: &str, range: &Range<'_>) -> Result<u64> {
+        let start = KeyBuilder::from_slice(range.start_key, 0, 0);
+        let end = KeyBuilder::from_slice(range.end_key, 0, 0);
+        let opts = IterOptions::new(Some(start), Some(end), true);
+        let mut it = self.iterator_opt(cf, opts)?;
+        let mut it_valid = it.seek(range.start_key)?;
+        let mut keys = 0;
+        while it_valid {
+            keys += 1;
+            it_valid = it.next()?;
+        }
+        Ok(keys)
+    }
+
     fn ingest_maybe_slowdown_writes(&self, cf: &str) -> Result<bool> {
         let handle = util::get_cf_handle(self.as_inner(), cf)?;
         if let Some(n) = util::get_cf_num_files_at_level(self.as_inner(), handle, 0) {
@@ -317,6 +381,27 @@ impl MiscExt for RocksEngine {
         Ok(ret)
     }
 
+    fn get_sst_files_cf(&self, cf: &str, range: &Range<'_>) -> Result<Vec<SstFileMeta>> {
+        let handle = util::get_cf_handle(self.as_inner(), cf)?;
+        let metadata = self.as_inner().get_column_family_meta_data(handle);
+        let mut files = Vec::new();
+        for (level, level_meta) in metadata.get_levels().iter().enumerate() {
+            for f in level_meta.get_files() {
+                if f.get_smallestkey() >= range.end_key || f.get_largestkey() < range.start_key {
+                    continue;
+                }
+                files.push(SstFileMeta {
+                    name: f.get_name(),
+                    level,
+                    size: f.get_size() as u64,
+                    smallest_key: f.get_smallestkey().to_vec(),
+                    largest_key: f.get_largestkey().to_vec(),
+                });
+            }
+        }
+        Ok(files)
+    }
+
     fn get_engine_used_size(&self) -> Result<u64> {
         let mut used_size: u64 = 0;
         for cf in self.cf_names() {
@@ -395,6 +480,22 @@ impl MiscExt for RocksEngine {
         }
     }
 
+    fn get_oldest_snapshot_age(&self) -> Result<Option<std::time::Duration>> {
+        let unix_secs = match self.as_inner().get_property_int(ROCKSDB_OLDEST_SNAPSHOT_TIME) {
+            // Some(0) indicates that no snapshot is in use.
+            None | Some(0) => return Ok(None),
+            Some(t) => t,
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| box_err!("clock went backwards: {:?}", e))?;
+        // The snapshot creation time is recorded at second granularity, so the
+        // clocks may be observed slightly out of order.
+        Ok(Some(
+            now.saturating_sub(std::time::Duration::from_secs(unix_secs)),
+        ))
+    }
+
     fn get_total_sst_files_size_cf(&self, cf: &str) -> Result<Option<u64>> {
         let handle = util::get_cf_handle(self.as_inner(), cf)?;
         Ok(self
@@ -402,6 +503,30 @@ impl MiscExt for RocksEngine {
             .get_property_int_cf(handle, ROCKSDB_TOTAL_SST_FILES_SIZE))
     }
 
+    // The block cache is shared, so reaching it through any CF is fine. These
+    // go through the default CF.
+
+    fn get_block_cache_capacity(&self) -> Result<u64> {
+        let handle = util::get_cf_handle(self.as_inner(), CF_DEFAULT)?;
+        Ok(self
+            .as_inner()
+            .get_options_cf(handle)
+            .get_block_cache_capacity())
+    }
+
+    fn set_block_cache_capacity(&self, capacity: u64) -> Result<()> {
+        let handle = util::get_cf_handle(self.as_inner(), CF_DEFAULT)?;
+        self.as_inner()
+            .get_options_cf(handle)
+            .set_block_cache_capacity(capacity)
+            .map_err(r2e)
+    }
+
+    fn get_block_cache_usage(&self) -> Result<u64> {
+        let handle = util::get_cf_handle(self.as_inner(), CF_DEFAULT)?;
+        Ok(self.as_inner().get_block_cache_usage_cf(handle))
+    }
+
     fn get_num_keys(&self) -> Result<u64> {
         let mut total = 0;
         for cf in self.cf_names() {
@@ -453,13 +578,14 @@ impl MiscExt for RocksEngine {
 #[cfg(test)]
 mod tests {
     use engine_traits::{
-        CompactExt, DeleteStrategy, Iterable, Iterator, Mutable, SyncMutable, WriteBatchExt,
-        ALL_CFS,
+        CfOptions, CompactExt, DbOptions, DeleteStrategy, Iterable, Iterator, Mutable, SyncMutable,
+        TitanCfOptions, WriteBatchExt, ALL_CFS,
     };
     use tempfile::Builder;
 
     use super::*;
     use crate::{
+        db_options::RocksTitanDbOptions,
         engine::RocksEngine,
         util::{new_engine, new_engine_opt},
         RocksCfOptions, RocksDbOptions,
@@ -616,6 +742,106 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_delete_ranges_auto() {
+        let path = Builder::new()
+            .prefix("test_delete_ranges_auto")
+            .tempdir()
+            .unwrap();
+        let db = new_engine(path.path().to_str().unwrap(), &[CF_DEFAULT]).unwrap();
+
+        let mut wb = db.write_batch();
+        for i in 0..10000u32 {
+            wb.put_cf(CF_DEFAULT, format!("k{:05}", i).as_bytes(), b"value")
+                .unwrap();
+        }
+        for i in 0..10u32 {
+            wb.put_cf(CF_DEFAULT, format!("z{:05}", i).as_bytes(), b"value")
+                .unwrap();
+        }
+        wb.write().unwrap();
+
+        let report = db
+            .delete_ranges_cf_report(
+                &WriteOptions::default(),
+                CF_DEFAULT,
+                DeleteStrategy::Auto,
+                &[Range::new(b"k", b"l"), Range::new(b"z", b"{")],
+            )
+            .unwrap();
+        assert!(report.written);
+        // The sparse range is deleted by key, the dense one by range.
+        assert_eq!(
+            report.applied,
+            vec![
+                AppliedDeleteStrategy::DeleteByKey,
+                AppliedDeleteStrategy::DeleteByRange
+            ]
+        );
+        check_data(&db, &[CF_DEFAULT], &[]);
+    }
+
+    #[test]
+    fn test_warmup_cf_range() {
+        let path = Builder::new()
+            .prefix("test_warmup_cf_range")
+            .tempdir()
+            .unwrap();
+        let db = new_engine(path.path().to_str().unwrap(), ALL_CFS).unwrap();
+        for i in 0..100u8 {
+            db.put(&[i], b"value").unwrap();
+        }
+        db.flush_cf("default", true).unwrap();
+
+        let handle = crate::util::get_cf_handle(db.as_inner(), "default").unwrap();
+        let usage_before = db.as_inner().get_block_cache_usage_cf(handle);
+        let keys = db
+            .warmup_cf_range("default", &Range::new(&[0], &[100]))
+            .unwrap();
+        assert_eq!(keys, 100);
+        // The scanned blocks are now resident in the block cache.
+        let usage_after = db.as_inner().get_block_cache_usage_cf(handle);
+        assert!(usage_after > usage_before);
+    }
+
+    #[test]
+    fn test_delete_ranges_report_fallback() {
+        let path = Builder::new()
+            .prefix("test_delete_ranges_report_fallback")
+            .tempdir()
+            .unwrap();
+        let sst_path = path.path().join("tmp_file").to_str().unwrap().to_owned();
+        let db = new_engine(path.path().to_str().unwrap(), ALL_CFS).unwrap();
+        for i in 0..9u8 {
+            db.put(&[b'k', i], b"value").unwrap();
+        }
+
+        // The second range overlaps the first, so `DeleteByWriter` degrades
+        // to key-deletes for it.
+        let report = db
+            .delete_ranges_cf_report(
+                &WriteOptions::default(),
+                "default",
+                DeleteStrategy::DeleteByWriter { sst_path },
+                &[
+                    Range::new(b"k\x00", b"k\x04"),
+                    Range::new(b"k\x02", b"k\x06"),
+                    Range::new(b"k\x06", b"k\x08"),
+                ],
+            )
+            .unwrap();
+        assert!(report.written);
+        assert_eq!(
+            report.applied,
+            vec![
+                AppliedDeleteStrategy::DeleteByWriter,
+                AppliedDeleteStrategy::DeleteByKey,
+                AppliedDeleteStrategy::DeleteByWriter,
+            ]
+        );
+        check_data(&db, &["default"], &[(&[b'k', 8], b"value")]);
+    }
+
     #[test]
     fn test_delete_all_files_in_range() {
         let path = Builder::new()
@@ -664,6 +890,126 @@ mod tests {
         check_data(&db, ALL_CFS, kvs_left.as_slice());
     }
 
+    #[test]
+    fn test_block_cache_capacity_and_usage() {
+        let path = Builder::new()
+            .prefix("engine_block_cache_capacity")
+            .tempdir()
+            .unwrap();
+        let db = new_engine(path.path().to_str().unwrap(), ALL_CFS).unwrap();
+
+        db.set_block_cache_capacity(64 << 20).unwrap();
+        assert_eq!(db.get_block_cache_capacity().unwrap(), 64 << 20);
+        // Shrinking online works as well.
+        db.set_block_cache_capacity(32 << 20).unwrap();
+        assert_eq!(db.get_block_cache_capacity().unwrap(), 32 << 20);
+
+        let usage_before = db.get_block_cache_usage().unwrap();
+        for i in 0..100u8 {
+            db.put(&[i], b"value").unwrap();
+        }
+        db.flush_cf("default", true).unwrap();
+        db.warmup_cf_range("default", &Range::new(&[0], &[100]))
+            .unwrap();
+        assert!(db.get_block_cache_usage().unwrap() > usage_before);
+    }
+
+    #[test]
+    fn test_get_sst_files_cf() {
+        let path = Builder::new()
+            .prefix("engine_get_sst_files_cf")
+            .tempdir()
+            .unwrap();
+        let db = new_engine(path.path().to_str().unwrap(), ALL_CFS).unwrap();
+
+        db.put(b"k1", b"v1").unwrap();
+        db.put(b"k2", b"v2").unwrap();
+        db.flush_cf("default", true).unwrap();
+        db.put(b"k3", b"v3").unwrap();
+        db.put(b"k4", b"v4").unwrap();
+        db.flush_cf("default", true).unwrap();
+
+        let files = db
+            .get_sst_files_cf("default", &Range::new(b"k1", b"k5"))
+            .unwrap();
+        assert_eq!(files.len(), 2);
+        for f in &files {
+            assert!(!f.name.is_empty());
+            assert_eq!(f.level, 0);
+            assert!(f.size > 0);
+        }
+
+        // The end key is exclusive, so the file starting at k3 is skipped.
+        let files = db
+            .get_sst_files_cf("default", &Range::new(b"k1", b"k3"))
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].smallest_key, b"k1");
+        assert_eq!(files[0].largest_key, b"k2");
+
+        assert!(
+            db.get_sst_files_cf("default", &Range::new(b"k5", b"k6"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_delete_blob_files_in_range_titan() {
+        let path = Builder::new()
+            .prefix("engine_delete_blob_files_in_range")
+            .tempdir()
+            .unwrap();
+        let path_str = path.path().to_str().unwrap();
+
+        let mut db_opts = RocksDbOptions::default();
+        let mut titan_db_opts = RocksTitanDbOptions::new();
+        titan_db_opts.set_min_blob_size(0);
+        db_opts.set_titandb_options(&titan_db_opts);
+        let mut cf_opts = RocksCfOptions::default();
+        let mut titan_cf_opts = RocksTitanDbOptions::new();
+        titan_cf_opts.set_min_blob_size(0);
+        cf_opts.set_titan_cf_options(&titan_cf_opts);
+        let db = new_engine_opt(path_str, db_opts, vec![("default", cf_opts)]).unwrap();
+        assert!(db.is_titan());
+
+        fn live_blob_files(db: &RocksEngine) -> u64 {
+            let handle = crate::util::get_cf_handle(db.as_inner(), "default").unwrap();
+            db.as_inner()
+                .get_property_int_cf(handle, ROCKSDB_TITANDB_NUM_LIVE_BLOB_FILE)
+                .unwrap()
+        }
+
+        // With a zero `min_blob_size` every flush produces one blob file.
+        for key in [b"k1", b"k2", b"k3", b"k4"] {
+            db.put(key, b"value").unwrap();
+            db.flush_cf("default", true).unwrap();
+        }
+        assert_eq!(live_blob_files(&db), 4);
+
+        // Drop the SST files first so the blob files in the range become
+        // eligible for deletion, then reclaim the blob space.
+        db.delete_ranges_cf(
+            &WriteOptions::default(),
+            "default",
+            DeleteStrategy::DeleteFiles,
+            &[Range::new(b"k2", b"k4")],
+        )
+        .unwrap();
+        let report = db
+            .delete_ranges_cf_report(
+                &WriteOptions::default(),
+                "default",
+                DeleteStrategy::DeleteBlobs,
+                &[Range::new(b"k2", b"k4")],
+            )
+            .unwrap();
+        assert!(!report.written);
+        assert_eq!(report.applied, vec![AppliedDeleteStrategy::DeleteBlobs]);
+        assert!(live_blob_files(&db) < 4);
+        check_data(&db, &["default"], &[(b"k1", b"value"), (b"k4", b"value")]);
+    }
+
     #[test]
     fn test_delete_range_prefix_bloom_case() {
         let path = Builder::new()
@@ -820,4 +1166,22 @@ mod tests {
         assert_eq!(db.get_total_sst_files_size_cf("lock").unwrap().unwrap(), 0);
         assert!(db.get_total_sst_files_size_cf("default").unwrap().unwrap() > 0);
     }
+
+    #[test]
+    fn test_get_oldest_snapshot_age() {
+        use engine_traits::KvEngine;
+
+        let path = Builder::new()
+            .prefix("test_oldest_snapshot_age")
+            .tempdir()
+            .unwrap();
+        let db = new_engine(path.path().to_str().unwrap(), ALL_CFS).unwrap();
+        db.put(b"k", b"v").unwrap();
+
+        assert!(db.get_oldest_snapshot_age().unwrap().is_none());
+        let snap = db.snapshot();
+        assert!(db.get_oldest_snapshot_age().unwrap().is_some());
+        drop(snap);
+        assert!(db.get_oldest_snapshot_age().unwrap().is_none());
+    }
 }
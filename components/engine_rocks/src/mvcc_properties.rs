@@ -76,3 +76,61 @@ impl MvccPropertiesExt for RocksEngine {
         Some(props)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use engine_traits::{MiscExt, SyncMutable, CF_WRITE, LARGE_CFS};
+    use txn_types::{Key, Write, WriteType};
+
+    use super::*;
+    use crate::{MvccPropertiesCollectorFactory, RocksCfOptions, RocksDbOptions};
+
+    #[test]
+    fn test_get_mvcc_properties_cf() {
+        let path = tempfile::Builder::new()
+            .prefix("_test_get_mvcc_properties_cf")
+            .tempdir()
+            .unwrap();
+        let path_str = path.path().to_str().unwrap();
+        let db_opts = RocksDbOptions::default();
+        let mut cf_opts = RocksCfOptions::default();
+        cf_opts.set_level_zero_file_num_compaction_trigger(10);
+        cf_opts.add_table_properties_collector_factory(
+            "tikv.mvcc-properties-collector",
+            MvccPropertiesCollectorFactory::default(),
+        );
+        let cfs_opts = LARGE_CFS.iter().map(|cf| (*cf, cf_opts.clone())).collect();
+        let db = crate::util::new_engine_opt(path_str, db_opts, cfs_opts).unwrap();
+
+        let mut put = |key: &[u8], ts: u64| {
+            let ts = ts.into();
+            let k = keys::data_key(Key::from_raw(key).append_ts(ts).as_encoded());
+            let v = Write::new(WriteType::Put, ts, None).as_ref().to_bytes();
+            db.put_cf(CF_WRITE, &k, &v).unwrap();
+        };
+        // One SST with versions at ts 2 and 3, another one at ts 10.
+        put(b"a", 2);
+        put(b"b", 3);
+        db.flush_cf(CF_WRITE, true).unwrap();
+        put(b"c", 10);
+        db.flush_cf(CF_WRITE, true).unwrap();
+
+        let start = keys::data_key(b"");
+        let end = keys::data_end_key(b"");
+        let props = db
+            .get_mvcc_properties_cf(CF_WRITE, 20.into(), &start, &end)
+            .unwrap();
+        assert_eq!(props.min_ts, 2.into());
+        assert_eq!(props.max_ts, 10.into());
+        assert_eq!(props.num_rows, 3);
+        assert_eq!(props.num_puts, 3);
+
+        // SSTs whose minimal ts is beyond the safe point are skipped.
+        let props = db
+            .get_mvcc_properties_cf(CF_WRITE, 5.into(), &start, &end)
+            .unwrap();
+        assert_eq!(props.min_ts, 2.into());
+        assert_eq!(props.max_ts, 3.into());
+        assert_eq!(props.num_rows, 2);
+    }
+}
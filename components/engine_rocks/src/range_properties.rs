@@ -214,3 +214,60 @@ impl RangePropertiesExt for RocksEngine {
         Ok(res)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use engine_traits::{SyncMutable, ALL_CFS};
+    use tempfile::Builder;
+
+    use super::*;
+    use crate::{
+        properties::RangePropertiesCollectorFactory, util::new_engine_opt, RocksCfOptions,
+        RocksDbOptions,
+    };
+
+    #[test]
+    fn test_get_range_approximate_split_keys_cf() {
+        let path = Builder::new()
+            .prefix("_test_get_range_approximate_split_keys_cf")
+            .tempdir()
+            .unwrap();
+        let path_str = path.path().to_str().unwrap();
+        let db_opts = RocksDbOptions::default();
+        let mut cf_opts = RocksCfOptions::default();
+        cf_opts.set_level_zero_file_num_compaction_trigger(10);
+        // Record an index entry for every key so the split points are exact.
+        let f = RangePropertiesCollectorFactory {
+            prop_size_index_distance: 1,
+            prop_keys_index_distance: 1,
+        };
+        cf_opts.add_table_properties_collector_factory("tikv.range-properties-collector", f);
+        let cfs_opts = ALL_CFS.iter().map(|cf| (*cf, cf_opts.clone())).collect();
+        let db = new_engine_opt(path_str, db_opts, cfs_opts).unwrap();
+
+        for i in 0..100u64 {
+            db.put_cf(CF_DEFAULT, format!("key_{:03}", i).as_bytes(), b"value")
+                .unwrap();
+        }
+        db.flush_cf(CF_DEFAULT, true).unwrap();
+
+        let range = Range::new(b"", b"zzzz");
+        let split_keys = db
+            .get_range_approximate_split_keys_cf(CF_DEFAULT, range, 4)
+            .unwrap();
+        // 4 split keys divide the 100 keys into 5 even sections.
+        let expected: Vec<Vec<u8>> = ["key_020", "key_040", "key_060", "key_080"]
+            .iter()
+            .map(|k| k.as_bytes().to_vec())
+            .collect();
+        assert_eq!(split_keys, expected);
+
+        // Requesting more split keys than there are index entries returns them
+        // all, sorted.
+        let all = db
+            .get_range_approximate_split_keys_cf(CF_DEFAULT, range, 1000)
+            .unwrap();
+        assert_eq!(all.len(), 100);
+        assert!(all.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -3,12 +3,16 @@
 use std::sync::Arc;
 
 use engine_traits::{self, Mutable, Result, WriteBatchExt, WriteOptions};
+use futures::future::BoxFuture;
 use rocksdb::{Writable, WriteBatch as RawWriteBatch, DB};
 
 use crate::{engine::RocksEngine, options::RocksWriteOptions, r2e, util::get_cf_handle};
 
 const WRITE_BATCH_MAX_BATCH_NUM: usize = 16;
 const WRITE_BATCH_MAX_KEY_NUM: usize = 16;
+// A batch may hold few keys but huge values, e.g. when applying prewrites of
+// large rows, and stall the write thread just as badly as one with many keys.
+const WRITE_BATCH_MAX_DATA_SIZE: usize = 16 * 1024 * 1024;
 
 impl WriteBatchExt for RocksEngine {
     type WriteBatch = RocksWriteBatchVec;
@@ -130,6 +134,57 @@ impl engine_traits::WriteBatch for RocksWriteBatchVec {
         self.write_impl(opts, cb)
     }
 
+    fn write_split_opt(&mut self, opts: &WriteOptions) -> Result<u64> {
+        if !self.support_write_batch_vec || self.index == 0 {
+            return self.write_opt(opts);
+        }
+        let opt: RocksWriteOptions = opts.into();
+        let raw = opt.into_raw();
+        // Sub-batches guarded by an outstanding save point have to stay in a
+        // single atomic write, otherwise a later rollback could span a commit.
+        let split_end = self.save_points.first().copied().unwrap_or(self.index);
+        let mut seq = 0;
+        for i in 0..split_end {
+            if self.wbs[i].is_empty() {
+                continue;
+            }
+            self.db
+                .write_callback(&self.wbs[i], &raw, |s| seq = s)
+                .map_err(r2e)?;
+        }
+        self.db
+            .multi_batch_write_callback(&self.wbs[split_end..=self.index], &raw, |s| seq = s)
+            .map_err(r2e)?;
+        Ok(seq)
+    }
+
+    fn write_async_opt(&mut self, opts: &WriteOptions) -> BoxFuture<'static, Result<u64>> {
+        // Raw write options hold an FFI pointer, so carry the portable
+        // options into the future and convert them at write time.
+        let opts = opts.clone();
+        let db = self.db.clone();
+        let support_write_batch_vec = self.support_write_batch_vec;
+        let index = self.index;
+        let wbs = std::mem::replace(&mut self.wbs, vec![RawWriteBatch::default()]);
+        self.save_points.clear();
+        self.index = 0;
+        // The write is deferred until first poll, so the caller can hand the
+        // future to an I/O pool and overlap the WAL fsync with its next batch.
+        Box::pin(futures::future::lazy(move |_| {
+            let opt: RocksWriteOptions = (&opts).into();
+            let raw = opt.into_raw();
+            let mut seq = 0;
+            if support_write_batch_vec {
+                db.multi_batch_write_callback(&wbs[0..=index], &raw, |s| seq = s)
+                    .map_err(r2e)?;
+            } else {
+                db.write_callback(&wbs[0], &raw, |s| seq = s)
+                    .map_err(r2e)?;
+            }
+            Ok(seq)
+        }))
+    }
+
     fn data_size(&self) -> usize {
         let mut size: usize = 0;
         for i in 0..=self.index {
@@ -147,6 +202,9 @@ impl engine_traits::WriteBatch for RocksWriteBatchVec {
     }
 
     fn should_write_to_engine(&self) -> bool {
+        if self.data_size() > WRITE_BATCH_MAX_DATA_SIZE {
+            return true;
+        }
         if self.support_write_batch_vec {
             self.index >= WRITE_BATCH_MAX_BATCH_NUM
         } else {
@@ -190,6 +248,10 @@ impl engine_traits::WriteBatch for RocksWriteBatchVec {
         Err(r2e("no save point"))
     }
 
+    fn save_point_depth(&self) -> usize {
+        self.save_points.len()
+    }
+
     fn merge(&mut self, other: Self) -> Result<()> {
         for wb in other.as_inner() {
             self.check_switch_batch();
@@ -335,4 +397,121 @@ mod tests {
         wb.clear();
         assert!(!wb.should_write_to_engine());
     }
+
+    #[test]
+    fn test_should_write_to_engine_by_data_size() {
+        let path = Builder::new()
+            .prefix("test-should-write-by-data-size")
+            .tempdir()
+            .unwrap();
+        let engine = new_engine_opt(
+            path.path().join("db").to_str().unwrap(),
+            RocksDbOptions::default(),
+            vec![(CF_DEFAULT, RocksCfOptions::default())],
+        )
+        .unwrap();
+        let mut wb = engine.write_batch();
+        // Few keys, but the serialized data exceeds the size threshold.
+        let value = vec![0u8; WRITE_BATCH_MAX_DATA_SIZE / 4 + 1];
+        for i in 0..4u8 {
+            wb.put(&[i], &value).unwrap();
+        }
+        assert!(wb.should_write_to_engine());
+        wb.clear();
+        assert!(!wb.should_write_to_engine());
+    }
+
+    #[test]
+    fn test_write_split() {
+        let path = Builder::new().prefix("test-write-split").tempdir().unwrap();
+        let opt = RawDBOptions::default();
+        opt.enable_multi_batch_write(true);
+        let engine = new_engine_opt(
+            path.path().join("db").to_str().unwrap(),
+            RocksDbOptions::from_raw(opt),
+            vec![(CF_DEFAULT, RocksCfOptions::default())],
+        )
+        .unwrap();
+
+        // Fill several sub-batches so there's something to split.
+        let mut wb = engine.write_batch();
+        for i in 0..3 * WRITE_BATCH_MAX_KEY_NUM as u8 {
+            wb.put(&[b'k', i], b"v").unwrap();
+        }
+        let seq = wb.write_split().unwrap();
+        assert!(seq > 0);
+        for i in 0..3 * WRITE_BATCH_MAX_KEY_NUM as u8 {
+            assert!(engine.get_value(&[b'k', i]).unwrap().is_some());
+        }
+
+        // Sub-batches behind an outstanding save point are still written.
+        let mut wb = engine.write_batch();
+        wb.put(b"a", b"v").unwrap();
+        wb.set_save_point();
+        for i in 0..3 * WRITE_BATCH_MAX_KEY_NUM as u8 {
+            wb.put(&[b's', i], b"v").unwrap();
+        }
+        wb.write_split().unwrap();
+        assert!(engine.get_value(b"a").unwrap().is_some());
+        for i in 0..3 * WRITE_BATCH_MAX_KEY_NUM as u8 {
+            assert!(engine.get_value(&[b's', i]).unwrap().is_some());
+        }
+    }
+
+    #[test]
+    fn test_write_async() {
+        let path = Builder::new().prefix("test-write-async").tempdir().unwrap();
+        let engine = new_engine_opt(
+            path.path().join("db").to_str().unwrap(),
+            RocksDbOptions::default(),
+            vec![(CF_DEFAULT, RocksCfOptions::default())],
+        )
+        .unwrap();
+        let mut wb = engine.write_batch();
+        for i in 0..2 * WRITE_BATCH_MAX_KEY_NUM as u8 {
+            wb.put(&[b'k', i], b"v").unwrap();
+        }
+        let fut = wb.write_async();
+        // The commands are moved into the future; the batch is reusable.
+        assert!(wb.is_empty());
+        wb.put(b"aaa", b"bbb").unwrap();
+
+        let seq = futures::executor::block_on(fut).unwrap();
+        assert!(seq > 0);
+        for i in 0..2 * WRITE_BATCH_MAX_KEY_NUM as u8 {
+            assert!(engine.get_value(&[b'k', i]).unwrap().is_some());
+        }
+        assert!(engine.get_value(b"aaa").unwrap().is_none());
+        wb.write().unwrap();
+        assert!(engine.get_value(b"aaa").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_save_point_depth() {
+        let path = Builder::new()
+            .prefix("test-save-point-depth")
+            .tempdir()
+            .unwrap();
+        let engine = new_engine_opt(
+            path.path().join("db").to_str().unwrap(),
+            RocksDbOptions::default(),
+            vec![(CF_DEFAULT, RocksCfOptions::default())],
+        )
+        .unwrap();
+        let mut wb = engine.write_batch();
+        assert_eq!(wb.save_point_depth(), 0);
+        wb.set_save_point();
+        wb.put(b"aaa", b"bbb").unwrap();
+        wb.set_save_point();
+        assert_eq!(wb.save_point_depth(), 2);
+        wb.pop_save_point().unwrap();
+        assert_eq!(wb.save_point_depth(), 1);
+        wb.rollback_to_save_point().unwrap();
+        assert_eq!(wb.save_point_depth(), 0);
+        wb.pop_save_point().unwrap_err();
+        // `clear` drops any leaked save points as well.
+        wb.set_save_point();
+        wb.clear();
+        assert_eq!(wb.save_point_depth(), 0);
+    }
 }
@@ -0,0 +1,18 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use crate::{cf_options::CfOptionsExt, errors::Result};
+
+/// Online creation and removal of column families.
+///
+/// This lets new features roll out a column family on an existing store
+/// instead of requiring a full data wipe. Implementations need exclusive
+/// access to the engine: managing column families while other handles to
+/// the same engine are alive is refused, so callers should do it during
+/// startup before the engine is shared.
+pub trait CfManageExt: CfOptionsExt {
+    /// Creates a column family with the given options.
+    fn create_cf(&mut self, name: &str, opts: Self::CfOptions) -> Result<()>;
+
+    /// Drops a column family and deletes its data.
+    fn drop_cf(&mut self, name: &str) -> Result<()>;
+}
@@ -8,6 +8,50 @@ pub trait CfOptionsExt {
 
     fn get_options_cf(&self, cf: &str) -> Result<Self::CfOptions>;
     fn set_options_cf(&self, cf: &str, options: &[(&str, &str)]) -> Result<()>;
+
+    /// A stable fingerprint of the effective options of `cf`, for detecting
+    /// config drift across restarts. Each option key/value is hashed with
+    /// CRC64 and the digests folded with xor, so the result is deterministic
+    /// and independent of option order.
+    fn cf_options_fingerprint(&self, cf: &str) -> Result<u64> {
+        let opts = self.get_options_cf(cf)?;
+        let kvs = [
+            (
+                "max_write_buffer_number",
+                opts.get_max_write_buffer_number() as u64,
+            ),
+            (
+                "level_zero_slowdown_writes_trigger",
+                opts.get_level_zero_slowdown_writes_trigger() as u64,
+            ),
+            (
+                "level_zero_stop_writes_trigger",
+                opts.get_level_zero_stop_writes_trigger() as u64,
+            ),
+            (
+                "soft_pending_compaction_bytes_limit",
+                opts.get_soft_pending_compaction_bytes_limit(),
+            ),
+            (
+                "hard_pending_compaction_bytes_limit",
+                opts.get_hard_pending_compaction_bytes_limit(),
+            ),
+            ("block_cache_capacity", opts.get_block_cache_capacity()),
+            ("target_file_size_base", opts.get_target_file_size_base()),
+            (
+                "disable_auto_compactions",
+                opts.get_disable_auto_compactions() as u64,
+            ),
+        ];
+        let mut checksum = 0;
+        for (k, v) in kvs {
+            let mut digest = crc64fast::Digest::new();
+            digest.write(k.as_bytes());
+            digest.write(&v.to_be_bytes());
+            checksum ^= digest.sum64();
+        }
+        Ok(checksum)
+    }
 }
 
 pub trait CfOptions {
@@ -9,6 +9,14 @@ pub trait Checkpointable {
 
     fn new_checkpointer(&self) -> Result<Self::Checkpointer>;
 
+    /// Creates a consistent hard-link checkpoint of the whole db at `path`
+    /// without stopping writes, flushing the memtable first so the result
+    /// doesn't depend on the WAL. A convenience over `new_checkpointer` for
+    /// backup and debugging tools.
+    fn create_checkpoint(&self, path: &Path) -> Result<()> {
+        self.new_checkpointer()?.create_at(path, None, 0)
+    }
+
     fn merge(&self, dbs: &[&Self]) -> Result<()>;
 }
 
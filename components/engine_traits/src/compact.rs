@@ -26,6 +26,11 @@ pub trait CompactExt: CfNamesExt {
         Ok(())
     }
 
+    /// Compacts all column families to the bottommost level and blocks until
+    /// the compactions finish. Intended for offline maintenance; consider
+    /// `exclusive_manual == false` if foreground traffic must keep flowing.
+    fn compact_all(&self, exclusive_manual: bool) -> Result<()>;
+
     /// Compacts the column families in the specified range by manual or not.
     fn compact_range_cf(
         &self,
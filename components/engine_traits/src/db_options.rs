@@ -8,6 +8,13 @@ pub trait DbOptionsExt {
 
     fn get_db_options(&self) -> Self::DbOptions;
     fn set_db_options(&self, options: &[(&str, &str)]) -> Result<()>;
+
+    /// Dynamically adjust how many bytes are written to the WAL before
+    /// calling sync, to smooth out WAL fsync spikes. `0` turns the
+    /// incremental syncing off.
+    fn set_wal_bytes_per_sync(&self, n: u64) -> Result<()> {
+        self.set_db_options(&[("wal_bytes_per_sync", &n.to_string())])
+    }
 }
 
 /// A handle to a database's options
@@ -16,6 +23,7 @@ pub trait DbOptions {
 
     fn new() -> Self;
     fn get_max_background_jobs(&self) -> i32;
+    fn get_wal_bytes_per_sync(&self) -> u64;
     fn get_rate_bytes_per_sec(&self) -> Option<i64>;
     fn set_rate_bytes_per_sec(&mut self, rate_bytes_per_sec: i64) -> Result<()>;
     fn get_rate_limiter_auto_tuned(&self) -> Option<bool>;
@@ -7,4 +7,8 @@ pub trait FlowControlFactorsExt {
     fn get_cf_num_immutable_mem_table(&self, cf: &str) -> Result<Option<u64>>;
 
     fn get_cf_pending_compaction_bytes(&self, cf: &str) -> Result<Option<u64>>;
+
+    /// Whether writes to the column family are currently delayed or stopped
+    /// by the engine's own stall mechanism.
+    fn get_cf_is_write_stalled(&self, cf: &str) -> Result<Option<bool>>;
 }
@@ -6,6 +6,14 @@ pub trait ImportExt {
     type IngestExternalFileOptions: IngestExternalFileOptions;
 
     fn ingest_external_file_cf(&self, cf: &str, files: &[&str]) -> Result<()>;
+
+    /// Same as `ingest_external_file_cf`, but with caller-provided options.
+    fn ingest_external_file_cf_opt(
+        &self,
+        cf: &str,
+        files: &[&str],
+        opts: &Self::IngestExternalFileOptions,
+    ) -> Result<()>;
 }
 
 pub trait IngestExternalFileOptions {
@@ -16,4 +24,11 @@ pub trait IngestExternalFileOptions {
     fn get_write_global_seqno(&self) -> bool;
 
     fn set_write_global_seqno(&mut self, f: bool);
+
+    fn get_allow_write(&self) -> bool;
+
+    /// Whether foreground writes are allowed while the ingestion is ongoing.
+    /// When set, the engine avoids blocking writers even if a memtable flush
+    /// is required.
+    fn set_allow_write(&mut self, f: bool);
 }
@@ -267,6 +267,8 @@ extern crate test;
 //
 // Many of these define "extension" traits, that end in `Ext`.
 
+mod cf_manage;
+pub use crate::cf_manage::*;
 mod cf_names;
 pub use crate::cf_names::*;
 mod cf_options;
@@ -36,6 +36,35 @@ pub enum DeleteStrategy {
     /// Delete by ingesting a SST file with deletions. Useful when the number of
     /// ranges is too many.
     DeleteByWriter { sst_path: String },
+    /// Let the engine pick a strategy for each range based on its approximate
+    /// number of keys (memtable and SST stats): ranges with few keys are
+    /// deleted key by key, the rest drop fully covered SST files first and
+    /// cover the remainder with a range tombstone.
+    Auto,
+}
+
+/// The concrete strategy that handled one range in
+/// `MiscExt::delete_ranges_cf_report`. It may differ from the requested
+/// `DeleteStrategy`, e.g. `DeleteByWriter` degrades to key-deletes for
+/// ranges that overlap a previously processed one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AppliedDeleteStrategy {
+    DeleteFiles,
+    DeleteBlobs,
+    DeleteByKey,
+    DeleteByRange,
+    DeleteByWriter,
+}
+
+/// The outcome of `MiscExt::delete_ranges_cf_report`, enumerating which
+/// concrete strategy handled each range so callers can log surprises.
+/// `applied` follows the engine's processing order, which may reorder the
+/// input ranges.
+#[derive(Clone, Debug, Default)]
+pub struct DeleteReport {
+    /// Whether there's data written through kv interface.
+    pub written: bool,
+    pub applied: Vec<AppliedDeleteStrategy>,
 }
 
 /// `StatisticsReporter` can be used to report engine's private statistics to
@@ -55,6 +84,16 @@ pub trait StatisticsReporter<T: ?Sized> {
     fn flush(&mut self);
 }
 
+/// Metadata of one SST file, as returned by `MiscExt::get_sst_files_cf`.
+#[derive(Clone, Debug)]
+pub struct SstFileMeta {
+    pub name: String,
+    pub level: usize,
+    pub size: u64,
+    pub smallest_key: Vec<u8>,
+    pub largest_key: Vec<u8>,
+}
+
 #[derive(Default)]
 pub struct RangeStats {
     // The number of entries
@@ -100,16 +139,39 @@ pub trait MiscExt: CfNamesExt + FlowControlFactorsExt + WriteBatchExt {
         cf: &str,
         strategy: DeleteStrategy,
         ranges: &[Range<'_>],
-    ) -> Result<bool>;
+    ) -> Result<bool> {
+        Ok(self
+            .delete_ranges_cf_report(wopts, cf, strategy, ranges)?
+            .written)
+    }
+
+    /// Same as `delete_ranges_cf`, but also reports which concrete strategy
+    /// handled each range. See `DeleteReport`.
+    fn delete_ranges_cf_report(
+        &self,
+        wopts: &WriteOptions,
+        cf: &str,
+        strategy: DeleteStrategy,
+        ranges: &[Range<'_>],
+    ) -> Result<DeleteReport>;
 
     /// Return the approximate number of records and size in the range of
     /// memtables of the cf.
     fn get_approximate_memtable_stats_cf(&self, cf: &str, range: &Range<'_>) -> Result<(u64, u64)>;
 
+    /// Iterates the range with `fill_cache` enabled to pull its blocks into
+    /// the block cache ahead of a latency-sensitive scan. Returns the number
+    /// of keys touched.
+    fn warmup_cf_range(&self, cf: &str, range: &Range<'_>) -> Result<u64>;
+
     fn ingest_maybe_slowdown_writes(&self, cf: &str) -> Result<bool>;
 
     fn get_sst_key_ranges(&self, cf: &str, level: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
 
+    /// Lists metadata of the SST files of the cf that overlap the given range,
+    /// so diagnostics can reason about the physical layout.
+    fn get_sst_files_cf(&self, cf: &str, range: &Range<'_>) -> Result<Vec<SstFileMeta>>;
+
     /// Gets total used size of rocksdb engine, including:
     /// * total size (bytes) of all SST files.
     /// * total size (bytes) of active and unflushed immutable memtables.
@@ -141,8 +203,23 @@ pub trait MiscExt: CfNamesExt + FlowControlFactorsExt + WriteBatchExt {
 
     fn get_oldest_snapshot_sequence_number(&self) -> Option<u64>;
 
+    /// How long the oldest unreleased snapshot has been held, or `None` when
+    /// no snapshot is held. Useful for alerting on stuck snapshots.
+    fn get_oldest_snapshot_age(&self) -> Result<Option<std::time::Duration>>;
+
     fn get_total_sst_files_size_cf(&self, cf: &str) -> Result<Option<u64>>;
 
+    /// The capacity of the block cache. The cache is shared between column
+    /// families, so this is an engine-level property.
+    fn get_block_cache_capacity(&self) -> Result<u64>;
+
+    /// Resizes the shared block cache online, e.g. when the memory pressure
+    /// of the store changes.
+    fn set_block_cache_capacity(&self, capacity: u64) -> Result<()>;
+
+    /// The memory currently used by the shared block cache.
+    fn get_block_cache_usage(&self) -> Result<u64>;
+
     fn get_num_keys(&self) -> Result<u64>;
 
     fn get_range_stats(&self, cf: &str, start: &[u8], end: &[u8]) -> Result<Option<RangeStats>>;
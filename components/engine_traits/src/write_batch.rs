@@ -1,5 +1,7 @@
 // Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
 
+use futures::future::BoxFuture;
+
 use crate::{errors::Result, options::WriteOptions};
 
 /// Engines that can create write batches
@@ -85,6 +87,44 @@ pub trait WriteBatch: Mutable {
         self.write_opt(&WriteOptions::default())
     }
 
+    /// Commit the WriteBatch to disk, letting the engine split an oversized
+    /// batch into several smaller writes so it doesn't stall the write thread.
+    ///
+    /// Atomicity is only guaranteed per sub-batch: a crash in the middle may
+    /// persist a prefix of the commands, so this must only be used when
+    /// replaying the whole batch is safe, e.g. applying raft logs below the
+    /// persisted apply index. Commands guarded by an outstanding save point
+    /// are never split apart. Engines that cannot split fall back to a plain
+    /// `write`. Returns the sequence number of the last write.
+    fn write_split(&mut self) -> Result<u64> {
+        self.write_split_opt(&WriteOptions::default())
+    }
+
+    /// Same as `write_split`, but with the given options.
+    fn write_split_opt(&mut self, opts: &WriteOptions) -> Result<u64> {
+        self.write_opt(opts)
+    }
+
+    /// Commit the WriteBatch to disk through a future.
+    ///
+    /// The commands are moved out of the batch, which is left empty and may
+    /// be reused right away, so the caller can start filling the next batch
+    /// while this write is in flight. Engines may defer the actual write
+    /// until the future is first polled, e.g. on an I/O pool, to overlap the
+    /// WAL fsync with the caller's work; the default implementation writes
+    /// synchronously and returns a resolved future. Resolves to the sequence
+    /// number of the write.
+    fn write_async(&mut self) -> BoxFuture<'static, Result<u64>> {
+        self.write_async_opt(&WriteOptions::default())
+    }
+
+    /// Same as `write_async`, but with the given options.
+    fn write_async_opt(&mut self, opts: &WriteOptions) -> BoxFuture<'static, Result<u64>> {
+        let res = self.write_opt(opts);
+        self.clear();
+        Box::pin(futures::future::ready(res))
+    }
+
     /// The data size of a write batch
     ///
     /// This is necessarily engine-dependent. In RocksDB though it appears to
@@ -98,7 +138,8 @@ pub trait WriteBatch: Mutable {
     /// Whether any commands have been issued to this batch
     fn is_empty(&self) -> bool;
 
-    /// Whether the number of commands exceeds WRITE_BATCH_MAX_KEYS
+    /// Whether the number of commands exceeds WRITE_BATCH_MAX_KEYS, or the
+    /// serialized data grows beyond an engine-specific size threshold
     ///
     /// If so, the `write` method should be called.
     fn should_write_to_engine(&self) -> bool;
@@ -121,6 +162,12 @@ pub trait WriteBatch: Mutable {
     /// Additionally pops the last save point from the save point stack.
     fn rollback_to_save_point(&mut self) -> Result<()>;
 
+    /// The number of save points currently on the save point stack
+    ///
+    /// Mostly a debug aid for asserting balanced set/pop pairs and detecting
+    /// leaked save points in tests.
+    fn save_point_depth(&self) -> usize;
+
     /// Merge another WriteBatch to itself
     fn merge(&mut self, src: Self) -> Result<()>;
 }
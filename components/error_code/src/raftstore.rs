@@ -37,6 +37,7 @@ define_error_codes!(
 
     SNAP_ABORT => ("SnapAbort", "", ""),
     SNAP_TOO_MANY => ("SnapTooMany", "", ""),
+    SNAP_CORRUPTED => ("SnapCorrupted", "", ""),
     SNAP_UNKNOWN => ("SnapUnknown", "", "")
 );
 
@@ -15,8 +15,8 @@ use tikv_util::time::{Instant, Limiter};
 
 use crate::{
     compression_reader_dispatcher, encrypt_wrap_reader, read_external_storage_into_file,
-    record_storage_create, BackendConfig, ExternalData, ExternalStorage, HdfsStorage, LocalStorage,
-    NoopStorage, RestoreConfig, UnpinReader,
+    record_storage_create, BackendConfig, ExternalData, ExternalStorage, HdfsStorage,
+    InstrumentedStorage, LimitedStorage, LocalStorage, NoopStorage, RestoreConfig, UnpinReader,
 };
 
 pub fn create_storage(
@@ -54,6 +54,7 @@ fn create_backend(
     backend_config: BackendConfig,
 ) -> io::Result<Box<dyn ExternalStorage>> {
     let start = Instant::now();
+    let rate_limit = backend_config.rate_limit;
     let storage: Box<dyn ExternalStorage> = match backend {
         Backend::Local(local) => {
             let p = Path::new(&local.path);
@@ -77,6 +78,15 @@ fn create_backend(
         #[allow(unreachable_patterns)]
         _ => return Err(bad_backend(backend.clone())),
     };
+    // Instrument the raw backend so the metrics reflect its actual
+    // throughput and latency rather than the rate limiter's throttling.
+    let storage = Box::new(InstrumentedStorage::new(storage)) as Box<dyn ExternalStorage>;
+    let storage = if rate_limit.is_finite() {
+        Box::new(LimitedStorage::new(Limiter::new(rate_limit), storage))
+            as Box<dyn ExternalStorage>
+    } else {
+        storage
+    };
     record_storage_create(start, &*storage);
     Ok(storage)
 }
@@ -148,6 +158,68 @@ mod tests {
         let backend = StorageBackend::default();
         assert!(create_storage(&backend, Default::default()).is_err());
     }
+
+    #[test]
+    fn test_create_gcs_storage() {
+        // Credentials are optional at creation time; requests would fail
+        // later without them, but mapping the backend must work.
+        let mut config = Gcs::default();
+        config.set_bucket("test-bucket".to_owned());
+        let backend = make_gcs_backend(config);
+        let storage = create_storage(&backend, Default::default()).unwrap();
+        assert_eq!(storage.name(), "gcs");
+
+        // A bucket-less config is rejected.
+        let backend = make_gcs_backend(Gcs::default());
+        create_storage(&backend, Default::default()).unwrap_err();
+    }
+
+    #[test]
+    fn test_create_azblob_storage() {
+        let mut config = AzureBlobStorage::default();
+        config.set_bucket("test-container".to_owned());
+        config.set_account_name("test-account".to_owned());
+        config.set_access_sig("sv=2021-08-06&sig=test".to_owned());
+        let backend = make_azblob_backend(config);
+        let storage = create_storage(&backend, Default::default()).unwrap();
+        assert_eq!(storage.name(), "azure");
+
+        // A bucket-less config is rejected.
+        let backend = make_azblob_backend(AzureBlobStorage::default());
+        create_storage(&backend, Default::default()).unwrap_err();
+    }
+
+    #[test]
+    fn test_create_rate_limited_storage() {
+        let temp_dir = Builder::new().tempdir().unwrap();
+        let backend = make_local_backend(temp_dir.path());
+        let config = BackendConfig {
+            rate_limit: 1024.0,
+            ..Default::default()
+        };
+        // The wrapper is transparent except for throttling.
+        let storage = create_storage(&backend, config).unwrap();
+        assert_eq!(storage.name(), "local");
+    }
+
+    #[test]
+    fn test_backend_config_upload_concurrency() {
+        use crate::DEFAULT_MAX_UPLOAD_CONCURRENCY;
+
+        let config = BackendConfig::default();
+        assert_eq!(config.max_upload_concurrency, DEFAULT_MAX_UPLOAD_CONCURRENCY);
+        assert!(config.max_upload_concurrency > 0);
+
+        let config = BackendConfig {
+            max_upload_concurrency: 2,
+            ..Default::default()
+        };
+        assert_eq!(config.max_upload_concurrency, 2);
+        // Local storage ignores the bound but still accepts the config.
+        let temp_dir = Builder::new().tempdir().unwrap();
+        let backend = make_local_backend(temp_dir.path());
+        create_storage(&backend, config).unwrap();
+    }
 }
 
 pub struct BlobStore<Blob: BlobStorage>(Blob);
@@ -3,8 +3,10 @@
 use std::{io, path, process::Stdio};
 
 use async_trait::async_trait;
+use futures_util::stream::TryStreamExt;
+use tikv_util::stream::error_stream;
 use tokio::{io as async_io, process::Command};
-use tokio_util::compat::FuturesAsyncReadCompatExt;
+use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
 use url::Url;
 
 use crate::{ExternalData, ExternalStorage, UnpinReader};
@@ -62,6 +64,39 @@ impl HdfsStorage {
         self.get_hadoop_home()
             .map(|hadoop| format!("{}/bin/hdfs", hadoop))
     }
+
+    /// Spawns `hdfs dfs -cat` streaming the object to its stdout.
+    fn spawn_cat(&self, name: &str) -> io::Result<tokio::process::ChildStdout> {
+        if name.contains(path::MAIN_SEPARATOR) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("[{}] parent is not allowed in storage", name),
+            ));
+        }
+        let cmd_path = self.get_hdfs_bin().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                "Cannot found hdfs command, please specify HADOOP_HOME",
+            )
+        })?;
+        let remote_url = self.remote.clone().join(name).unwrap();
+        let path = try_convert_to_path(&remote_url);
+
+        let mut cmd_with_args = vec![];
+        let user = self.get_linux_user();
+        if let Some(user) = &user {
+            cmd_with_args.extend(["sudo", "-u", user]);
+        }
+        cmd_with_args.extend([&cmd_path, "dfs", "-cat", path]);
+        info!("calling hdfs"; "cmd" => ?cmd_with_args);
+        let mut hdfs_cmd = Command::new(cmd_with_args[0])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .args(&cmd_with_args[1..])
+            .spawn()?;
+        Ok(hdfs_cmd.stdout.take().unwrap())
+    }
 }
 
 const STORAGE_NAME: &str = "hdfs";
@@ -131,8 +166,11 @@ impl ExternalStorage for HdfsStorage {
         }
     }
 
-    fn read(&self, _name: &str) -> ExternalData<'_> {
-        unimplemented!("currently only HDFS export is implemented")
+    fn read(&self, name: &str) -> ExternalData<'_> {
+        match self.spawn_cat(name) {
+            Ok(stdout) => Box::new(stdout.compat()),
+            Err(e) => Box::new(error_stream(e).into_async_read()),
+        }
     }
 
     fn read_part(&self, _name: &str, _off: u64, _len: u64) -> ExternalData<'_> {
@@ -170,6 +208,27 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_read_spawn_failure() {
+        use futures_util::AsyncReadExt;
+
+        let backend = HdfsStorage::new(
+            "hdfs://",
+            HdfsConfig {
+                hadoop_home: "/nonexistent/hadoop".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        // The hdfs binary does not exist, so the error surfaces on read.
+        let mut buf = Vec::new();
+        backend
+            .read("a.log")
+            .read_to_end(&mut buf)
+            .await
+            .unwrap_err();
+    }
+
     #[test]
     fn test_try_convert_to_path() {
         let url = Url::parse("hdfs:///some/path").unwrap();
@@ -0,0 +1,198 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_trait::async_trait;
+use futures_io::AsyncRead;
+use tikv_util::time::Instant;
+
+use crate::{
+    metrics::{
+        EXT_STORAGE_BYTES_COUNTER, EXT_STORAGE_FAILURE_COUNTER,
+        EXT_STORAGE_REQUEST_DURATION_HISTOGRAM,
+    },
+    ExternalData, ExternalStorage, UnpinReader,
+};
+
+/// A wrapper that reports throughput, latency and failures of the inner
+/// storage to Prometheus, labeled by the backend name and the operation.
+///
+/// Writes are recorded when the upload finishes. Reads return a lazy stream,
+/// so their bytes are counted as the caller consumes the stream and the
+/// duration covers the whole lifetime of the reader.
+pub struct InstrumentedStorage<S> {
+    inner: S,
+}
+
+impl<S> InstrumentedStorage<S> {
+    pub fn new(inner: S) -> Self {
+        InstrumentedStorage { inner }
+    }
+}
+
+#[async_trait]
+impl<S: ExternalStorage> ExternalStorage for InstrumentedStorage<S> {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn url(&self) -> io::Result<url::Url> {
+        self.inner.url()
+    }
+
+    async fn write(&self, name: &str, reader: UnpinReader, content_length: u64) -> io::Result<()> {
+        let start = Instant::now();
+        let res = self.inner.write(name, reader, content_length).await;
+        let ty = self.inner.name();
+        EXT_STORAGE_REQUEST_DURATION_HISTOGRAM
+            .with_label_values(&[ty, "write"])
+            .observe(start.saturating_elapsed().as_secs_f64());
+        match &res {
+            Ok(()) => EXT_STORAGE_BYTES_COUNTER
+                .with_label_values(&[ty, "write"])
+                .inc_by(content_length),
+            Err(_) => EXT_STORAGE_FAILURE_COUNTER
+                .with_label_values(&[ty, "write"])
+                .inc(),
+        }
+        res
+    }
+
+    fn read(&self, name: &str) -> ExternalData<'_> {
+        Box::new(InstrumentedReader::new(
+            self.inner.read(name),
+            self.inner.name(),
+            "read",
+        ))
+    }
+
+    fn read_part(&self, name: &str, off: u64, len: u64) -> ExternalData<'_> {
+        Box::new(InstrumentedReader::new(
+            self.inner.read_part(name, off, len),
+            self.inner.name(),
+            "read_part",
+        ))
+    }
+}
+
+/// Counts the bytes pulled through the inner reader and records the total
+/// duration when it is dropped.
+struct InstrumentedReader<R> {
+    inner: R,
+    ty: &'static str,
+    operation: &'static str,
+    start: Instant,
+}
+
+impl<R> InstrumentedReader<R> {
+    fn new(inner: R, ty: &'static str, operation: &'static str) -> Self {
+        InstrumentedReader {
+            inner,
+            ty,
+            operation,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for InstrumentedReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let res = Pin::new(&mut this.inner).poll_read(cx, buf);
+        match &res {
+            Poll::Ready(Ok(n)) => EXT_STORAGE_BYTES_COUNTER
+                .with_label_values(&[this.ty, this.operation])
+                .inc_by(*n as u64),
+            Poll::Ready(Err(_)) => EXT_STORAGE_FAILURE_COUNTER
+                .with_label_values(&[this.ty, this.operation])
+                .inc(),
+            Poll::Pending => {}
+        }
+        res
+    }
+}
+
+impl<R> Drop for InstrumentedReader<R> {
+    fn drop(&mut self) {
+        EXT_STORAGE_REQUEST_DURATION_HISTOGRAM
+            .with_label_values(&[self.ty, self.operation])
+            .observe(self.start.saturating_elapsed().as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::AsyncReadExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_instrumented_storage() {
+        let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+        let ls = crate::LocalStorage::new(temp_dir.path()).unwrap();
+        let storage = InstrumentedStorage::new(ls);
+
+        let written_before = EXT_STORAGE_BYTES_COUNTER
+            .with_label_values(&["local", "write"])
+            .get();
+        let read_before = EXT_STORAGE_BYTES_COUNTER
+            .with_label_values(&["local", "read"])
+            .get();
+
+        let magic_contents: &[u8] = b"measure me";
+        storage
+            .write(
+                "a.log",
+                UnpinReader(Box::new(magic_contents)),
+                magic_contents.len() as u64,
+            )
+            .await
+            .unwrap();
+        let mut buf = Vec::new();
+        storage.read("a.log").read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, magic_contents);
+
+        let len = magic_contents.len() as u64;
+        assert_eq!(
+            EXT_STORAGE_BYTES_COUNTER
+                .with_label_values(&["local", "write"])
+                .get()
+                - written_before,
+            len
+        );
+        assert_eq!(
+            EXT_STORAGE_BYTES_COUNTER
+                .with_label_values(&["local", "read"])
+                .get()
+                - read_before,
+            len
+        );
+
+        let failures_before = EXT_STORAGE_FAILURE_COUNTER
+            .with_label_values(&["local", "write"])
+            .get();
+        storage
+            .write(
+                "/absolute",
+                UnpinReader(Box::new(magic_contents)),
+                magic_contents.len() as u64,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            EXT_STORAGE_FAILURE_COUNTER
+                .with_label_values(&["local", "write"])
+                .get()
+                - failures_before,
+            1
+        );
+    }
+}
@@ -17,7 +17,7 @@ use std::{
 
 use async_compression::futures::bufread::ZstdDecoder;
 use async_trait::async_trait;
-use encryption::{DecrypterReader, FileEncryptionInfo, Iv};
+use encryption::{DecrypterReader, EncrypterReader, FileEncryptionInfo, Iv};
 use file_system::File;
 use futures::io::BufReader;
 use futures_io::AsyncRead;
@@ -37,6 +37,12 @@ pub mod local;
 pub use local::LocalStorage;
 mod noop;
 pub use noop::NoopStorage;
+mod instrumented;
+pub use instrumented::InstrumentedStorage;
+mod limited;
+pub use limited::LimitedStorage;
+mod retriable;
+pub use retriable::RetriableStorage;
 mod metrics;
 use metrics::EXT_STORAGE_CREATE_HISTOGRAM;
 mod export;
@@ -56,10 +62,31 @@ pub struct UnpinReader(pub Box<dyn AsyncRead + Unpin + Send>);
 
 pub type ExternalData<'a> = Box<dyn AsyncRead + Unpin + Send + 'a>;
 
-#[derive(Debug, Default)]
+/// Default bound on in-flight part uploads for multipart-capable backends.
+pub const DEFAULT_MAX_UPLOAD_CONCURRENCY: usize = 8;
+
+#[derive(Debug)]
 pub struct BackendConfig {
     pub s3_multi_part_size: usize,
     pub hdfs_config: HdfsConfig,
+    /// Upper bound on concurrently uploaded parts of one object, so large
+    /// multipart uploads don't buffer unbounded memory. Backends without
+    /// multipart support (e.g. local) ignore it.
+    pub max_upload_concurrency: usize,
+    /// Bytes-per-second cap applied to the created storage; `f64::INFINITY`
+    /// leaves it unthrottled.
+    pub rate_limit: f64,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        BackendConfig {
+            s3_multi_part_size: 0,
+            hdfs_config: HdfsConfig::default(),
+            max_upload_concurrency: DEFAULT_MAX_UPLOAD_CONCURRENCY,
+            rate_limit: f64::INFINITY,
+        }
+    }
 }
 
 #[derive(Debug, Default)]
@@ -104,12 +131,93 @@ pub trait ExternalStorage: 'static + Send + Sync {
     /// Write all contents of the read to the given path.
     async fn write(&self, name: &str, reader: UnpinReader, content_length: u64) -> io::Result<()>;
 
+    /// Write a batch of small objects, returning the per-item results in
+    /// input order. The default implementation simply loops over `write`;
+    /// backends may override it to upload items concurrently.
+    async fn write_batch(
+        &self,
+        items: Vec<(String, UnpinReader, u64)>,
+    ) -> io::Result<Vec<io::Result<()>>> {
+        let mut results = Vec::with_capacity(items.len());
+        for (name, reader, content_length) in items {
+            results.push(self.write(&name, reader, content_length).await);
+        }
+        Ok(results)
+    }
+
+    /// Writes the object, then reads it back and verifies the stored bytes
+    /// hash to the same SHA-256 as the streamed content, turning silent
+    /// upload truncation or corruption into an `InvalidData` error. The
+    /// content is buffered in memory, so this is only suitable for
+    /// reasonably small objects.
+    async fn write_checked(
+        &self,
+        name: &str,
+        mut reader: UnpinReader,
+        content_length: u64,
+    ) -> io::Result<()> {
+        let mut content = Vec::with_capacity(content_length as usize);
+        reader.0.read_to_end(&mut content).await?;
+        let expected = object_sha256(&content)?;
+        self.write(
+            name,
+            UnpinReader(Box::new(std::io::Cursor::new(content))),
+            content_length,
+        )
+        .await?;
+
+        let mut stored = Vec::with_capacity(content_length as usize);
+        self.read(name).read_to_end(&mut stored).await?;
+        let actual = object_sha256(&stored)?;
+        if actual != expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "sha256 of stored object {} not match, expect: {:?}, calculate: {:?}",
+                    name, expected, actual,
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Like `write`, but runs the content through AES-CTR with
+    /// `file_crypter` before it leaves the process, so the object is
+    /// encrypted at rest even on backends without native server-side
+    /// encryption. CTR mode keeps the content length unchanged.
+    async fn write_encrypted(
+        &self,
+        name: &str,
+        reader: UnpinReader,
+        content_length: u64,
+        file_crypter: FileEncryptionInfo,
+    ) -> io::Result<()> {
+        let reader = Box::new(EncrypterReader::new(
+            reader.0,
+            file_crypter.method,
+            &file_crypter.key,
+            Iv::from_slice(&file_crypter.iv)?,
+        )?);
+        self.write(name, UnpinReader(reader), content_length).await
+    }
+
     /// Read all contents of the given path.
     fn read(&self, name: &str) -> ExternalData<'_>;
 
     /// Read part of contents of the given path.
     fn read_part(&self, name: &str, off: u64, len: u64) -> ExternalData<'_>;
 
+    /// Read up to `n` leading bytes of the given path, e.g. to sniff a magic
+    /// header without fetching the whole object. Returns fewer bytes if the
+    /// object is shorter than `n`.
+    async fn read_prefix(&self, name: &str, n: usize) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(n);
+        self.read_part(name, 0, n as u64)
+            .read_to_end(&mut buf)
+            .await?;
+        Ok(buf)
+    }
+
     /// Read from external storage and restore to the given path
     async fn restore(
         &self,
@@ -169,6 +277,13 @@ impl ExternalStorage for Arc<dyn ExternalStorage> {
         (**self).write(name, reader, content_length).await
     }
 
+    async fn write_batch(
+        &self,
+        items: Vec<(String, UnpinReader, u64)>,
+    ) -> io::Result<Vec<io::Result<()>>> {
+        (**self).write_batch(items).await
+    }
+
     fn read(&self, name: &str) -> ExternalData<'_> {
         (**self).read(name)
     }
@@ -177,6 +292,10 @@ impl ExternalStorage for Arc<dyn ExternalStorage> {
         (**self).read_part(name, off, len)
     }
 
+    async fn read_prefix(&self, name: &str, n: usize) -> io::Result<Vec<u8>> {
+        (**self).read_prefix(name, n).await
+    }
+
     async fn restore(
         &self,
         storage_name: &str,
@@ -211,6 +330,13 @@ impl ExternalStorage for Box<dyn ExternalStorage> {
         self.as_ref().write(name, reader, content_length).await
     }
 
+    async fn write_batch(
+        &self,
+        items: Vec<(String, UnpinReader, u64)>,
+    ) -> io::Result<Vec<io::Result<()>>> {
+        self.as_ref().write_batch(items).await
+    }
+
     fn read(&self, name: &str) -> ExternalData<'_> {
         self.as_ref().read(name)
     }
@@ -219,6 +345,10 @@ impl ExternalStorage for Box<dyn ExternalStorage> {
         self.as_ref().read_part(name, off, len)
     }
 
+    async fn read_prefix(&self, name: &str, n: usize) -> io::Result<Vec<u8>> {
+        self.as_ref().read_prefix(name, n).await
+    }
+
     async fn restore(
         &self,
         storage_name: &str,
@@ -239,6 +369,11 @@ impl ExternalStorage for Box<dyn ExternalStorage> {
     }
 }
 
+fn object_sha256(content: &[u8]) -> io::Result<Vec<u8>> {
+    file_system::sha256(content)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("sha256 failed: {}", err)))
+}
+
 /// Wrap the reader with file_crypter.
 /// Return the reader directly if file_crypter is None.
 pub fn encrypt_wrap_reader(
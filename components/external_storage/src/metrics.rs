@@ -11,4 +11,29 @@ lazy_static! {
         exponential_buckets(0.00001, 2.0, 26).unwrap()
     )
     .unwrap();
+    pub static ref EXT_STORAGE_RETRY_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "tikv_external_storage_retry_total",
+        "Number of retried external storage operations",
+        &["operation"]
+    )
+    .unwrap();
+    pub static ref EXT_STORAGE_REQUEST_DURATION_HISTOGRAM: HistogramVec = register_histogram_vec!(
+        "tikv_external_storage_request_duration_seconds",
+        "Bucketed histogram of external storage request duration",
+        &["type", "operation"],
+        exponential_buckets(0.001, 2.0, 22).unwrap()
+    )
+    .unwrap();
+    pub static ref EXT_STORAGE_BYTES_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "tikv_external_storage_bytes_total",
+        "Number of bytes transferred to and from external storage",
+        &["type", "operation"]
+    )
+    .unwrap();
+    pub static ref EXT_STORAGE_FAILURE_COUNTER: IntCounterVec = register_int_counter_vec!(
+        "tikv_external_storage_failure_total",
+        "Number of failed external storage operations",
+        &["type", "operation"]
+    )
+    .unwrap();
 }
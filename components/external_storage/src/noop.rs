@@ -75,6 +75,23 @@ mod tests {
         assert!(buf.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_write_checked_detects_loss() {
+        let noop = NoopStorage::default();
+
+        // The noop backend drops the content, so the read-back hash differs.
+        let magic_contents: &[u8] = b"5678";
+        let err = noop
+            .write_checked(
+                "a.log",
+                UnpinReader(Box::new(magic_contents)),
+                magic_contents.len() as u64,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
     #[test]
     fn test_url_of_backend() {
         assert_eq!(url_for().to_string(), "noop:///");
@@ -0,0 +1,160 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::io;
+
+use async_trait::async_trait;
+use futures_util::AsyncReadExt;
+use tikv_util::stream::{retry_ext, RetryError, RetryExt};
+
+use crate::{
+    metrics::EXT_STORAGE_RETRY_COUNTER, ExternalData, ExternalStorage, UnpinReader,
+};
+
+/// A wrapper that retries transient IO failures of the inner storage with
+/// truncated exponential backoff and jitter (see `tikv_util::stream::retry`).
+///
+/// `write` buffers the content in memory so the upload can be replayed, and
+/// is therefore only suitable for reasonably small objects. `read` returns a
+/// lazy stream and is not retried here; callers resuming a broken download
+/// should use `read_part` from the last good offset.
+pub struct RetriableStorage<S> {
+    inner: S,
+}
+
+impl<S> RetriableStorage<S> {
+    pub fn new(inner: S) -> Self {
+        RetriableStorage { inner }
+    }
+}
+
+#[async_trait]
+impl<S: ExternalStorage> ExternalStorage for RetriableStorage<S> {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn url(&self) -> io::Result<url::Url> {
+        self.inner.url()
+    }
+
+    async fn write(
+        &self,
+        name: &str,
+        mut reader: UnpinReader,
+        content_length: u64,
+    ) -> io::Result<()> {
+        let mut content = Vec::with_capacity(content_length as usize);
+        reader.0.read_to_end(&mut content).await?;
+        retry_ext(
+            || {
+                let reader = UnpinReader(Box::new(std::io::Cursor::new(content.clone())));
+                self.inner.write(name, reader, content_length)
+            },
+            RetryExt::default().with_fail_hook(|e: &io::Error| {
+                if e.is_retryable() {
+                    EXT_STORAGE_RETRY_COUNTER.with_label_values(&["write"]).inc();
+                }
+            }),
+        )
+        .await
+    }
+
+    fn read(&self, name: &str) -> ExternalData<'_> {
+        self.inner.read(name)
+    }
+
+    fn read_part(&self, name: &str, off: u64, len: u64) -> ExternalData<'_> {
+        self.inner.read_part(name, off, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::AsyncReadExt;
+
+    use super::*;
+
+    /// Fails the first `fail_times` writes with a retryable error.
+    struct FlakyStorage {
+        inner: crate::LocalStorage,
+        fail_times: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ExternalStorage for FlakyStorage {
+        fn name(&self) -> &'static str {
+            self.inner.name()
+        }
+
+        fn url(&self) -> io::Result<url::Url> {
+            self.inner.url()
+        }
+
+        async fn write(
+            &self,
+            name: &str,
+            reader: UnpinReader,
+            content_length: u64,
+        ) -> io::Result<()> {
+            if self
+                .fail_times
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "injected"));
+            }
+            self.inner.write(name, reader, content_length).await
+        }
+
+        fn read(&self, name: &str) -> ExternalData<'_> {
+            self.inner.read(name)
+        }
+
+        fn read_part(&self, name: &str, off: u64, len: u64) -> ExternalData<'_> {
+            self.inner.read_part(name, off, len)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retriable_write() {
+        let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+        let flaky = FlakyStorage {
+            inner: crate::LocalStorage::new(temp_dir.path()).unwrap(),
+            fail_times: AtomicUsize::new(2),
+        };
+        let storage = RetriableStorage::new(flaky);
+
+        let magic_contents: &[u8] = b"retry me";
+        storage
+            .write(
+                "a.log",
+                UnpinReader(Box::new(magic_contents)),
+                magic_contents.len() as u64,
+            )
+            .await
+            .unwrap();
+        let mut buf = Vec::new();
+        storage.read("a.log").read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, magic_contents);
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_error_propagates() {
+        let temp_dir = tempfile::Builder::new().tempdir().unwrap();
+        let ls = crate::LocalStorage::new(temp_dir.path()).unwrap();
+        let storage = RetriableStorage::new(ls);
+
+        // An absolute object name is rejected with a permanent error.
+        let magic_contents: &[u8] = b"nope";
+        storage
+            .write(
+                "/absolute",
+                UnpinReader(Box::new(magic_contents)),
+                magic_contents.len() as u64,
+            )
+            .await
+            .unwrap_err();
+    }
+}
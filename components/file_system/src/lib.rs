@@ -468,6 +468,22 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_with_io_type() {
+        set_io_type(IoType::Other);
+        {
+            let _guard = WithIoType::new(IoType::Compaction);
+            assert_eq!(get_io_type(), IoType::Compaction);
+            {
+                let _nested = WithIoType::new(IoType::Flush);
+                assert_eq!(get_io_type(), IoType::Flush);
+            }
+            // Dropping the nested guard restores the enclosing type.
+            assert_eq!(get_io_type(), IoType::Compaction);
+        }
+        assert_eq!(get_io_type(), IoType::Other);
+    }
+
     #[test]
     fn test_get_file_size() {
         let tmp_dir = TempDir::new().unwrap();
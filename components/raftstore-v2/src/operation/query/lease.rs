@@ -123,6 +123,7 @@ impl<EK: KvEngine, ER: RaftEngine> Peer<EK, ER> {
                 // A read request proposed in the current lease is found; combine the new
                 // read request to that previous one, so that no proposing needed.
                 read.push_command(req, ch, commit_index);
+                ctx.raft_metrics.propose.batch_read_index.inc();
                 return;
             }
         }
@@ -14,7 +14,7 @@ use protobuf::Message;
 use raft::eraftpb;
 use tikv_util::box_try;
 
-use super::{split_observer::SplitObserver, *};
+use super::{merge_observer::MergeObserver, split_observer::SplitObserver, *};
 use crate::store::BucketRange;
 
 /// A handle for coprocessor to schedule some command back to raftstore.
@@ -485,7 +485,8 @@ impl<E: KvEngine> CoprocessorHost<E> {
             400,
             BoxSplitCheckObserver::new(TableCheckObserver::default()),
         );
-        registry.register_admin_observer(100, BoxAdminObserver::new(SplitObserver));
+        registry.register_admin_observer(100, BoxAdminObserver::new(SplitObserver::default()));
+        registry.register_admin_observer(100, BoxAdminObserver::new(MergeObserver));
         CoprocessorHost { registry, cfg }
     }
 
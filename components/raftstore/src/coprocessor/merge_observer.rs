@@ -0,0 +1,241 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use kvproto::{
+    metapb::Region,
+    raft_cmdpb::{AdminCmdType, AdminRequest},
+};
+use thiserror::Error;
+use tikv_util::{box_err, error, store::region_on_same_stores};
+
+use super::{AdminObserver, Coprocessor, ObserverContext, Result as CopResult};
+use crate::store::util;
+
+/// Reasons why `MergeObserver` rejects a merge proposal. The message is
+/// carried in the proposal error so it's surfaced to the scheduler (and
+/// eventually PD) instead of being detected during apply, where an illegal
+/// merge is fatal.
+#[derive(Debug, Error, PartialEq)]
+pub enum MergeCheckError {
+    #[error("region {0} is uninitialized, reject merge")]
+    Uninitialized(u64),
+    #[error(
+        "region {other_id} overlaps with region {region_id}, its epoch is stale or a split is \
+         pending, reject merge"
+    )]
+    Overlap { region_id: u64, other_id: u64 },
+    #[error("region {other_id} is not adjacent to region {region_id}, reject merge")]
+    NotAdjacent { region_id: u64, other_id: u64 },
+    #[error("region {other_id} and region {region_id} are not on the same stores, reject merge")]
+    PeersMismatch { region_id: u64, other_id: u64 },
+}
+
+fn regions_overlap(lhs: &Region, rhs: &Region) -> bool {
+    (rhs.get_end_key().is_empty() || lhs.get_start_key() < rhs.get_end_key())
+        && (lhs.get_end_key().is_empty() || rhs.get_start_key() < lhs.get_end_key())
+}
+
+/// Validates that the counterpart of a merge is a well-formed sibling of the
+/// current region before the command is proposed.
+fn check_merge_peer(region: &Region, other: &Region) -> Result<(), MergeCheckError> {
+    if other.get_peers().is_empty() || other.get_region_epoch().get_version() == 0 {
+        return Err(MergeCheckError::Uninitialized(other.get_id()));
+    }
+    if !util::is_sibling_regions(other, region) {
+        // Distinguish an overlapping range, which means one of the two
+        // descriptors predates a split, from plain non-adjacency.
+        if regions_overlap(other, region) {
+            return Err(MergeCheckError::Overlap {
+                region_id: region.get_id(),
+                other_id: other.get_id(),
+            });
+        }
+        return Err(MergeCheckError::NotAdjacent {
+            region_id: region.get_id(),
+            other_id: other.get_id(),
+        });
+    }
+    if !region_on_same_stores(other, region) {
+        return Err(MergeCheckError::PeersMismatch {
+            region_id: region.get_id(),
+            other_id: other.get_id(),
+        });
+    }
+    Ok(())
+}
+
+/// `MergeObserver` rejects `PrepareMerge`/`CommitMerge` proposals whose
+/// counterpart region is uninitialized, not adjacent, overlapping (a stale
+/// epoch or pending split) or placed on different stores.
+#[derive(Clone, Default)]
+pub struct MergeObserver;
+
+impl Coprocessor for MergeObserver {}
+
+impl AdminObserver for MergeObserver {
+    fn pre_propose_admin(
+        &self,
+        ctx: &mut ObserverContext<'_>,
+        req: &mut AdminRequest,
+    ) -> CopResult<()> {
+        let other = match req.get_cmd_type() {
+            AdminCmdType::PrepareMerge => {
+                if !req.has_prepare_merge() {
+                    return Err(box_err!(
+                        "cmd_type is PrepareMerge but it doesn't have prepare merge request, \
+                         message maybe corrupted!"
+                    ));
+                }
+                req.get_prepare_merge().get_target()
+            }
+            AdminCmdType::CommitMerge => {
+                if !req.has_commit_merge() {
+                    return Err(box_err!(
+                        "cmd_type is CommitMerge but it doesn't have commit merge request, \
+                         message maybe corrupted!"
+                    ));
+                }
+                req.get_commit_merge().get_source()
+            }
+            _ => return Ok(()),
+        };
+        if let Err(e) = check_merge_peer(ctx.region(), other) {
+            error!(
+                "reject merge proposal";
+                "region_id" => ctx.region().get_id(),
+                "cmd_type" => ?req.get_cmd_type(),
+                "err" => %e,
+            );
+            return Err(box_err!(e));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kvproto::{
+        metapb::{Peer, RegionEpoch},
+        raft_cmdpb::{CommitMergeRequest, PrepareMergeRequest},
+    };
+
+    use super::*;
+
+    fn new_region(id: u64, start_key: &[u8], end_key: &[u8], stores: &[u64]) -> Region {
+        let mut region = Region::default();
+        region.set_id(id);
+        region.set_start_key(start_key.to_vec());
+        region.set_end_key(end_key.to_vec());
+        let mut epoch = RegionEpoch::default();
+        epoch.set_version(2);
+        epoch.set_conf_ver(2);
+        region.set_region_epoch(epoch);
+        for &store_id in stores {
+            let mut peer = Peer::default();
+            peer.set_id(id * 10 + store_id);
+            peer.set_store_id(store_id);
+            region.mut_peers().push(peer);
+        }
+        region
+    }
+
+    fn new_prepare_merge_request(target: Region) -> AdminRequest {
+        let mut req = AdminRequest::default();
+        req.set_cmd_type(AdminCmdType::PrepareMerge);
+        let mut prepare_merge = PrepareMergeRequest::default();
+        prepare_merge.set_target(target);
+        req.set_prepare_merge(prepare_merge);
+        req
+    }
+
+    fn new_commit_merge_request(source: Region) -> AdminRequest {
+        let mut req = AdminRequest::default();
+        req.set_cmd_type(AdminCmdType::CommitMerge);
+        let mut commit_merge = CommitMergeRequest::default();
+        commit_merge.set_source(source);
+        req.set_commit_merge(commit_merge);
+        req
+    }
+
+    #[test]
+    fn test_check_merge_peer() {
+        let region = new_region(1, b"b", b"d", &[1, 2, 3]);
+
+        // Siblings on both sides are accepted.
+        let left = new_region(2, b"a", b"b", &[1, 2, 3]);
+        let right = new_region(3, b"d", b"f", &[1, 2, 3]);
+        check_merge_peer(&region, &left).unwrap();
+        check_merge_peer(&region, &right).unwrap();
+
+        // An uninitialized target must be rejected.
+        let mut uninit = new_region(4, b"d", b"f", &[]);
+        assert_eq!(
+            check_merge_peer(&region, &uninit).unwrap_err(),
+            MergeCheckError::Uninitialized(4)
+        );
+        uninit = new_region(4, b"d", b"f", &[1, 2, 3]);
+        uninit.mut_region_epoch().set_version(0);
+        assert_eq!(
+            check_merge_peer(&region, &uninit).unwrap_err(),
+            MergeCheckError::Uninitialized(4)
+        );
+
+        // A target whose range overlaps the region carries a stale epoch,
+        // e.g. it predates the split that created the region.
+        let stale = new_region(5, b"a", b"c", &[1, 2, 3]);
+        assert_eq!(
+            check_merge_peer(&region, &stale).unwrap_err(),
+            MergeCheckError::Overlap {
+                region_id: 1,
+                other_id: 5
+            }
+        );
+
+        // A disjoint target is not adjacent.
+        let disjoint = new_region(6, b"e", b"f", &[1, 2, 3]);
+        assert_eq!(
+            check_merge_peer(&region, &disjoint).unwrap_err(),
+            MergeCheckError::NotAdjacent {
+                region_id: 1,
+                other_id: 6
+            }
+        );
+
+        // A sibling on different stores can not be merged.
+        let moved = new_region(7, b"d", b"f", &[1, 2, 4]);
+        assert_eq!(
+            check_merge_peer(&region, &moved).unwrap_err(),
+            MergeCheckError::PeersMismatch {
+                region_id: 1,
+                other_id: 7
+            }
+        );
+    }
+
+    #[test]
+    fn test_pre_propose_merge() {
+        let region = new_region(1, b"b", b"d", &[1, 2, 3]);
+        let mut ctx = ObserverContext::new(&region);
+        let observer = MergeObserver;
+
+        // Other admin commands are left untouched.
+        let mut req = AdminRequest::default();
+        observer.pre_propose_admin(&mut ctx, &mut req).unwrap();
+
+        let mut req = new_prepare_merge_request(new_region(2, b"d", b"f", &[1, 2, 3]));
+        observer.pre_propose_admin(&mut ctx, &mut req).unwrap();
+
+        let mut req = new_prepare_merge_request(new_region(2, b"c", b"f", &[1, 2, 3]));
+        observer.pre_propose_admin(&mut ctx, &mut req).unwrap_err();
+
+        let mut req = new_commit_merge_request(new_region(2, b"a", b"b", &[1, 2, 3]));
+        observer.pre_propose_admin(&mut ctx, &mut req).unwrap();
+
+        let mut req = new_commit_merge_request(new_region(2, b"a", b"b", &[1, 2, 4]));
+        observer.pre_propose_admin(&mut ctx, &mut req).unwrap_err();
+
+        // A corrupted request without the merge body is rejected.
+        let mut req = AdminRequest::default();
+        req.set_cmd_type(AdminCmdType::PrepareMerge);
+        observer.pre_propose_admin(&mut ctx, &mut req).unwrap_err();
+    }
+}
@@ -22,6 +22,7 @@ pub mod config;
 mod consistency_check;
 pub mod dispatcher;
 mod error;
+pub mod merge_observer;
 mod metrics;
 pub mod region_info_accessor;
 mod split_check;
@@ -38,6 +39,7 @@ pub use self::{
         StoreHandle,
     },
     error::{Error, Result},
+    merge_observer::{MergeCheckError, MergeObserver},
     region_info_accessor::{
         Callback as RegionInfoCallback, RangeKey, RegionCollector, RegionInfo, RegionInfoAccessor,
         RegionInfoProvider, SeekRegionCallback,
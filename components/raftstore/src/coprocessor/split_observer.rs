@@ -26,6 +26,30 @@ pub fn strip_timestamp_if_exists(mut key: Vec<u8>) -> Vec<u8> {
     key
 }
 
+/// Align a split key (already stripped of its timestamp) to at most
+/// `prefix_len` bytes of its raw form, re-encoding when the key is in encoded
+/// form. Keys whose raw form is not longer than the prefix are kept as they
+/// are.
+pub fn align_to_prefix_if_needed(key: Vec<u8>, prefix_len: usize) -> Vec<u8> {
+    let mut slice = key.as_slice();
+    match bytes::decode_bytes(&mut slice, false) {
+        Ok(mut raw) => {
+            if raw.len() <= prefix_len {
+                key
+            } else {
+                raw.truncate(prefix_len);
+                bytes::encode_bytes(&raw)
+            }
+        }
+        // It must be a raw key, truncate it directly.
+        Err(_) => {
+            let mut key = key;
+            key.truncate(prefix_len);
+            key
+        }
+    }
+}
+
 pub fn is_valid_split_key(key: &[u8], index: usize, region: &Region) -> bool {
     if key.is_empty() {
         warn!(
@@ -55,12 +79,46 @@ pub fn is_valid_split_key(key: &[u8], index: usize, region: &Region) -> bool {
     true
 }
 
+/// Policy used by `SplitObserver` to adjust proposed split keys.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SplitKeyPolicy {
+    /// Strip the MVCC timestamp from encoded keys so that multiple versions
+    /// of a key won't be separated into two regions.
+    StripTimestamp,
+    /// After stripping the timestamp, truncate the key to at most this many
+    /// bytes of its raw form, so that all keys sharing the prefix (e.g. a
+    /// table or row prefix in raw mode) stay in the same region.
+    AlignToPrefix(usize),
+}
+
 /// `SplitObserver` adjusts the split key so that it won't separate
 /// multiple MVCC versions of a key into two regions.
 #[derive(Clone)]
-pub struct SplitObserver;
+pub struct SplitObserver {
+    policy: SplitKeyPolicy,
+}
+
+impl Default for SplitObserver {
+    fn default() -> SplitObserver {
+        SplitObserver::new(SplitKeyPolicy::StripTimestamp)
+    }
+}
 
 impl SplitObserver {
+    pub fn new(policy: SplitKeyPolicy) -> SplitObserver {
+        SplitObserver { policy }
+    }
+
+    fn adjust_key(&self, key: Vec<u8>) -> Vec<u8> {
+        let key = strip_timestamp_if_exists(key);
+        match self.policy {
+            SplitKeyPolicy::StripTimestamp => key,
+            SplitKeyPolicy::AlignToPrefix(prefix_len) => {
+                align_to_prefix_if_needed(key, prefix_len)
+            }
+        }
+    }
+
     fn on_split(
         &self,
         ctx: &mut ObserverContext<'_>,
@@ -71,7 +129,7 @@ impl SplitObserver {
             .enumerate()
             .filter_map(|(i, mut split)| {
                 let key = split.take_split_key();
-                let key = strip_timestamp_if_exists(key);
+                let key = self.adjust_key(key);
                 if is_valid_split_key(&key, i, ctx.region) {
                     split.split_key = key;
                     Some(split)
@@ -223,7 +281,7 @@ mod tests {
         r.set_start_key(region_start_key);
 
         let mut ctx = ObserverContext::new(&r);
-        let observer = SplitObserver;
+        let observer = SplitObserver::default();
 
         let mut req = new_batch_split_request(vec![key]);
         observer.pre_propose_admin(&mut ctx, &mut req).unwrap();
@@ -244,7 +302,7 @@ mod tests {
         let mut ctx = ObserverContext::new(&region);
         let mut req = AdminRequest::default();
 
-        let observer = SplitObserver;
+        let observer = SplitObserver::default();
 
         // since no split is defined, actual coprocessor won't be invoke.
         observer.pre_propose_admin(&mut ctx, &mut req).unwrap();
@@ -308,6 +366,10 @@ mod tests {
         req.mut_splits().set_right_derive(true);
         observer.pre_propose_admin(&mut ctx, &mut req).unwrap();
         assert!(req.get_splits().get_right_derive());
+        check_expected_split_keys(&req, expected_keys);
+    }
+
+    fn check_expected_split_keys(req: &AdminRequest, expected_keys: Vec<Vec<u8>>) {
         assert_eq!(req.get_splits().get_requests().len(), expected_keys.len());
         for (i, (req, expected_key)) in req
             .get_splits()
@@ -324,4 +386,35 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_split_align_to_prefix() {
+        let region = Region::default();
+        let mut ctx = ObserverContext::new(&region);
+        let observer = SplitObserver::new(SplitKeyPolicy::AlignToPrefix(4));
+
+        let mut split_keys = Vec::new();
+        let mut expected_keys = Vec::new();
+
+        // Raw keys are truncated to the prefix directly, and keys sharing a
+        // prefix collapse into one split.
+        split_keys.push(b"abcd1111".to_vec());
+        split_keys.push(b"abcd2222".to_vec());
+        expected_keys.push(b"abcd".to_vec());
+
+        // Keys shorter than the prefix are preserved.
+        split_keys.push(b"xyz".to_vec());
+        expected_keys.push(b"xyz".to_vec());
+
+        // Encoded keys are decoded, truncated and re-encoded, with the
+        // timestamp stripped first.
+        let mut key = encode_bytes(b"zzzz5678");
+        key.write_u64::<BigEndian>(0).unwrap();
+        split_keys.push(key);
+        expected_keys.push(encode_bytes(b"zzzz"));
+
+        let mut req = new_batch_split_request(split_keys);
+        observer.pre_propose_admin(&mut ctx, &mut req).unwrap();
+        check_expected_split_keys(&req, expected_keys);
+    }
 }
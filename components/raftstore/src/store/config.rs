@@ -185,6 +185,15 @@ pub struct Config {
     #[online_config(hidden)]
     pub leader_transfer_max_log_lag: u64,
 
+    /// The maximum number of leader transfers scheduled by PD that can be
+    /// started on the store within `pd_transfer_leader_min_interval`, so
+    /// that rolling restarts don't cause election storms. 0 means no limit.
+    #[online_config(skip)]
+    pub max_pd_transfer_leaders_per_interval: u64,
+    /// The sliding window used by `max_pd_transfer_leaders_per_interval`.
+    #[online_config(skip)]
+    pub pd_transfer_leader_min_interval: ReadableDuration,
+
     #[online_config(skip)]
     pub snap_apply_batch_size: ReadableSize,
 
@@ -462,6 +471,8 @@ impl Default for Config {
             abnormal_leader_missing_duration: ReadableDuration::minutes(10),
             peer_stale_state_check_interval: ReadableDuration::minutes(5),
             leader_transfer_max_log_lag: 128,
+            max_pd_transfer_leaders_per_interval: 0,
+            pd_transfer_leader_min_interval: ReadableDuration::secs(1),
             snap_apply_batch_size: ReadableSize::mb(10),
             snap_apply_copy_symlink: false,
             region_worker_tick_interval: if cfg!(feature = "test") {
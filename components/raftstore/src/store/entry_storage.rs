@@ -1828,6 +1828,42 @@ pub mod tests {
         store.compact_entry_cache(6);
     }
 
+    #[test]
+    fn test_evict_entry_cache() {
+        let ents = vec![new_entry(3, 3), new_entry(4, 4), new_entry(5, 5)];
+        let td = Builder::new().prefix("tikv-store-test").tempdir().unwrap();
+        let worker = LazyWorker::new("snap-manager");
+        let sched = worker.scheduler();
+        let (dummy_scheduler, _) = dummy_scheduler();
+        let mut store = new_storage_from_ents(sched, dummy_scheduler, &td, &ents);
+        store.cache.cache.clear();
+
+        let entries = vec![
+            new_entry(6, 5),
+            new_entry(7, 5),
+            new_entry(8, 5),
+            new_entry(9, 5),
+        ];
+        append_ents(&mut store, &entries);
+        validate_cache(&store, &entries);
+
+        // Only persisted entries can be evicted.
+        store.cache.persisted = 9;
+
+        // Evicting half of the cache keeps the latest entries.
+        store.evict_entry_cache(true);
+        validate_cache(&store, &entries[3..]);
+
+        // A full eviction clears the cache.
+        store.evict_entry_cache(false);
+        assert!(store.is_entry_cache_empty());
+
+        // Evicting an empty cache is a no-op.
+        store.evict_entry_cache(true);
+        store.evict_entry_cache(false);
+        assert!(store.is_entry_cache_empty());
+    }
+
     #[test]
     fn test_async_warm_up_entry_cache() {
         let ents = vec![new_entry(4, 4), new_entry(5, 5), new_entry(6, 6)];
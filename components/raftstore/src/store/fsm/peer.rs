@@ -68,7 +68,7 @@ use super::life::forward_destroy_to_source_peer;
 #[cfg(any(test, feature = "testexport"))]
 use crate::store::PeerInternalStat;
 use crate::{
-    coprocessor::{RegionChangeEvent, RegionChangeReason},
+    coprocessor::{get_region_approximate_middle, RegionChangeEvent, RegionChangeReason},
     store::{
         cmd_resp::{bind_term, new_error},
         demote_failed_voters_request,
@@ -5929,7 +5929,7 @@ where
     fn on_prepare_split_region(
         &mut self,
         region_epoch: metapb::RegionEpoch,
-        split_keys: Vec<Vec<u8>>,
+        mut split_keys: Vec<Vec<u8>>,
         cb: Callback<EK::Snapshot>,
         source: &str,
         share_source_region_size: bool,
@@ -5955,6 +5955,31 @@ where
             )));
             return;
         }
+        if split_keys.is_empty() {
+            // No split key is supplied, choose the approximate middle key of
+            // the region from SST properties so that the two halves are
+            // balanced by size. The chosen key still goes through
+            // `SplitObserver` when the batch split is proposed.
+            match get_region_approximate_middle(&self.ctx.engines.kv, self.region()) {
+                Ok(Some(key)) => split_keys.push(keys::origin_key(&key).to_vec()),
+                Ok(None) => {
+                    cb.invoke_with_response(new_error(box_err!(
+                        "{} failed to get approximate middle key, the region may be too small \
+                         to split",
+                        self.fsm.peer.tag
+                    )));
+                    return;
+                }
+                Err(e) => {
+                    cb.invoke_with_response(new_error(box_err!(
+                        "{} failed to get approximate middle key {:?}",
+                        self.fsm.peer.tag,
+                        e
+                    )));
+                    return;
+                }
+            }
+        }
         if let Err(e) = util::validate_split_region(
             self.fsm.region_id(),
             self.fsm.peer_id(),
@@ -110,3 +110,65 @@ impl HibernateState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_region(peers: &[u64]) -> Region {
+        let mut region = Region::default();
+        for &id in peers {
+            let mut peer = kvproto::metapb::Peer::default();
+            peer.set_id(id);
+            region.mut_peers().push(peer);
+        }
+        region
+    }
+
+    #[test]
+    fn test_maybe_hibernate() {
+        let region = new_region(&[1, 2, 3]);
+        let mut state = HibernateState::ordered();
+
+        // The first check only starts polling.
+        assert!(!state.maybe_hibernate(1, &region));
+        // Can't hibernate until all peers have voted.
+        assert!(!state.maybe_hibernate(1, &region));
+        state.count_vote(2);
+        // Duplicated votes are counted only once.
+        state.count_vote(2);
+        assert!(!state.maybe_hibernate(1, &region));
+        state.count_vote(3);
+        assert!(state.maybe_hibernate(1, &region));
+        // Hibernated state is kept until reset.
+        assert!(state.maybe_hibernate(1, &region));
+
+        // Waking up the group restarts the negotiation.
+        state.reset(GroupState::Ordered);
+        assert_eq!(state.group_state(), GroupState::Ordered);
+        assert!(!state.maybe_hibernate(1, &region));
+
+        // Votes from removed peers must not be counted.
+        state.count_vote(2);
+        state.count_vote(4);
+        assert!(!state.maybe_hibernate(1, &region));
+        state.count_vote(3);
+        assert!(state.maybe_hibernate(1, &region));
+    }
+
+    #[test]
+    fn test_reset_keeps_leader_state_within_idle() {
+        let region = new_region(&[1, 2]);
+        let mut state = HibernateState::ordered();
+        state.reset(GroupState::Idle);
+        assert!(!state.maybe_hibernate(1, &region));
+        state.count_vote(2);
+        assert!(state.maybe_hibernate(1, &region));
+        // Resetting to the same state is a no-op.
+        state.reset(GroupState::Idle);
+        assert!(state.maybe_hibernate(1, &region));
+        // Any other state awakens the leader.
+        state.reset(GroupState::Chaos);
+        assert!(!state.maybe_hibernate(1, &region));
+    }
+}
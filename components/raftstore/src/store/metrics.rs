@@ -192,6 +192,7 @@ make_static_metric! {
         transfer_leader,
         conf_change,
         batch,
+        batch_read_index,
         dropped_read_index,
     }
 
@@ -708,6 +709,13 @@ lazy_static! {
             "Pending read index count."
         ).unwrap();
 
+    pub static ref RAFT_READ_INDEX_BATCH_SIZE: Histogram =
+        register_histogram!(
+            "tikv_raftstore_read_index_batch_size",
+            "Number of read commands sharing one read index round trip.",
+            exponential_buckets(1.0, 2.0, 12).unwrap() // max 2048
+        ).unwrap();
+
     pub static ref READ_QPS_TOPN: GaugeVec =
         register_gauge_vec!(
             "tikv_read_qps_topn",
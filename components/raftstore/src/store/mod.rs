@@ -70,7 +70,7 @@ pub use self::{
     replication_mode::{GlobalReplicationState, StoreGroup},
     snap::{
         check_abort, copy_snapshot,
-        snap_io::{apply_sst_cf_file, build_sst_cf_file_list},
+        snap_io::{apply_sst_cf_file, build_sst_cf_file_list, StaleDetector},
         ApplyOptions, CfFile, Error as SnapError, SnapEntry, SnapKey, SnapManager,
         SnapManagerBuilder, Snapshot, SnapshotStatistics, TabletSnapKey, TabletSnapManager,
     },
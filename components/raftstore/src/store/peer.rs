@@ -3933,6 +3933,7 @@ where
                     // A read request proposed in the current lease is found; combine the new
                     // read request to that previous one, so that no proposing needed.
                     read.push_command(req, cb, commit_index);
+                    poll_ctx.raft_metrics.propose.batch_read_index.inc();
                     return false;
                 }
             }
@@ -5856,6 +5857,53 @@ mod tests {
     use super::*;
     use crate::store::{msg::ExtCallback, util::u64_to_timespec};
 
+    #[test]
+    fn test_split_check_trigger() {
+        let mut t = SplitCheckTrigger::default();
+        // A fresh trigger must not skip the check, as there is no
+        // approximate stats yet.
+        assert!(!t.should_skip(100));
+
+        t.post_triggered();
+        assert!(t.should_skip(100));
+
+        // Accumulated apply-time deltas beyond the threshold force a check.
+        t.add_size_diff(60);
+        t.add_size_diff(60);
+        assert!(!t.should_skip(100));
+        // Negative deltas (e.g. from deletions) saturate at zero.
+        t.add_size_diff(-1000);
+        assert!(t.should_skip(100));
+
+        // Reconciliation reporting a splitable region keeps the check
+        // scheduled even after it's triggered.
+        t.on_approximate_region_size(Some(1000), Some(true));
+        assert_eq!(t.approximate_size, Some(1000));
+        assert!(!t.should_skip(100));
+        t.post_triggered();
+        assert!(!t.should_skip(100));
+
+        // Once the region is not splitable any more, the check can be skipped
+        // again.
+        t.on_approximate_region_size(None, Some(false));
+        assert_eq!(t.approximate_size, Some(1000));
+        t.post_triggered();
+        assert!(t.should_skip(100));
+
+        // Ingesting an SST updates the maintained stats and requires a
+        // re-check as the file may overlap with existing data.
+        t.on_ingest_sst_result(24, 16);
+        assert_eq!(t.approximate_size, Some(1024));
+        assert_eq!(t.approximate_keys, Some(16));
+        assert!(!t.should_skip(100));
+
+        t.post_triggered();
+        t.on_clear_region_size();
+        assert_eq!(t.approximate_size, None);
+        assert_eq!(t.approximate_keys, None);
+        assert!(!t.should_skip(100));
+    }
+
     #[test]
     fn test_sync_log() {
         let white_list = [
@@ -1217,7 +1217,10 @@ pub mod tests {
     use crate::{
         coprocessor::CoprocessorHost,
         store::{
-            async_io::{read::ReadRunner, write::write_to_db_for_test},
+            async_io::{
+                read::ReadRunner,
+                write::{write_to_db_for_test, ExtraWrite},
+            },
             bootstrap_store,
             entry_storage::tests::validate_cache,
             fsm::apply::compact_raft_log,
@@ -1907,6 +1910,47 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_storage_apply_witness_snapshot() {
+        let ents = vec![new_entry(3, 3), new_entry(4, 4), new_entry(5, 5)];
+        let td1 = Builder::new().prefix("tikv-store-test").tempdir().unwrap();
+        let worker = LazyWorker::new("snap-manager");
+        let sched = worker.scheduler();
+        let (dummy_scheduler, _) = dummy_scheduler();
+        let s1 = new_storage_from_ents(sched.clone(), dummy_scheduler.clone(), &td1, &ents);
+        // A witness snapshot carries no data CFs, only the raft metadata and
+        // the region state.
+        let snap = util::new_empty_snapshot(
+            s1.region().clone(),
+            s1.applied_index(),
+            s1.applied_term(),
+            true,
+        );
+
+        let td2 = Builder::new().prefix("tikv-store-test").tempdir().unwrap();
+        let mut s2 = new_storage(sched, dummy_scheduler, &td2);
+        let mut write_task = WriteTask::new(s2.get_region_id(), s2.peer_id, 1);
+        let (snap_region, for_witness) = s2.apply_snapshot(&snap, &mut write_task, &[]).unwrap();
+        assert!(for_witness);
+        assert_eq!(snap_region, *s1.region());
+        assert_eq!(s2.apply_state().get_applied_index(), 5);
+        assert_eq!(s2.raft_state().get_last_index(), 5);
+
+        match &mut write_task.extra_write {
+            ExtraWrite::V1(kv_wb) => kv_wb.write().unwrap(),
+            _ => panic!("expect v1 extra write"),
+        }
+        // No data needs to be applied asynchronously in the region worker, so
+        // the region state is written as `Normal` directly.
+        let state: RegionLocalState = s2
+            .engines
+            .kv
+            .get_msg_cf(CF_RAFT, &keys::region_state_key(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.get_state(), PeerState::Normal);
+    }
+
     #[test]
     fn test_storage_apply_snapshot() {
         let ents = vec![
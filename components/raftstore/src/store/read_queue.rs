@@ -75,6 +75,7 @@ impl<C> ReadIndexRequest<C> {
     }
 
     pub fn take_cmds(&mut self) -> MustConsumeVec<(RaftCmdRequest, C, Option<u64>)> {
+        RAFT_READ_INDEX_BATCH_SIZE.observe(self.cmds.len() as f64);
         self.cmds_heap_size = 0;
         self.cmds.take()
     }
@@ -14,7 +14,7 @@ use std::{
     thread, time, u64,
 };
 
-use collections::{HashMap, HashMapEntry as Entry};
+use collections::{HashMap, HashMapEntry as Entry, HashSet};
 use encryption::{create_aes_ctr_crypter, DataKeyManager, Iv};
 use engine_traits::{CfName, KvEngine, CF_DEFAULT, CF_LOCK, CF_WRITE};
 use error_code::{self, ErrorCode, ErrorCodeExt};
@@ -59,6 +59,11 @@ pub const SNAPSHOT_CFS_ENUM_PAIR: &[(CfNames, CfName)] = &[
 pub const SNAPSHOT_VERSION: u64 = 2;
 pub const TABLET_SNAPSHOT_VERSION: u64 = 3;
 pub const IO_LIMITER_CHUNK_SIZE: usize = 4 * 1024;
+/// How often a paused snapshot apply rechecks whether it may continue.
+const APPLY_PAUSE_CHECK_INTERVAL_MS: u64 = 100;
+/// Plain cf files of at least this size are converted into an SST and
+/// ingested, instead of being replayed key by key through a write batch.
+const PLAIN_FILE_INGEST_THRESHOLD: u64 = 4 * 1024 * 1024;
 
 /// Name prefix for the self-generated snapshot file.
 const SNAP_GEN_PREFIX: &str = "gen";
@@ -82,6 +87,13 @@ pub enum Error {
     #[error("too many snapshots")]
     TooManySnapshots,
 
+    #[error("snapshot cf file {path} corrupted, checksum {got}, expected {expected}")]
+    Corrupted {
+        path: String,
+        got: u32,
+        expected: u32,
+    },
+
     #[error("snap failed {0:?}")]
     Other(#[from] Box<dyn StdError + Sync + Send>),
 }
@@ -105,6 +117,7 @@ impl ErrorCodeExt for Error {
         match self {
             Error::Abort => error_code::raftstore::SNAP_ABORT,
             Error::TooManySnapshots => error_code::raftstore::SNAP_TOO_MANY,
+            Error::Corrupted { .. } => error_code::raftstore::SNAP_CORRUPTED,
             Error::Other(_) => error_code::raftstore::SNAP_UNKNOWN,
         }
     }
@@ -280,12 +293,12 @@ fn check_file_checksum(
     path: &Path,
 ) -> RaftStoreResult<()> {
     if got_checksum != expected_checksum {
-        return Err(box_err!(
-            "invalid checksum {} for snapshot cf file {}, expected {}",
-            got_checksum,
-            path.display(),
-            expected_checksum
-        ));
+        return Err(Error::Corrupted {
+            path: path.display().to_string(),
+            got: got_checksum,
+            expected: expected_checksum,
+        }
+        .into());
     }
     Ok(())
 }
@@ -446,6 +459,13 @@ pub struct Snapshot {
     cf_file_index: usize,
     meta_file: MetaFile,
     hold_tmp_files: bool,
+    // Bytes already persisted by a previous receive attempt. Incoming bytes up
+    // to this offset are dropped instead of being written again, see
+    // `new_for_receiving`.
+    recv_resume_offset: u64,
+    // Whether partially received cf files survive a failed receive, so that a
+    // later attempt for the same key can resume from them.
+    keep_partial_on_drop: bool,
 
     mgr: SnapManagerCore,
 }
@@ -508,6 +528,8 @@ impl Snapshot {
             cf_file_index: 0,
             meta_file,
             hold_tmp_files: false,
+            recv_resume_offset: 0,
+            keep_partial_on_drop: false,
             mgr: mgr.clone(),
         };
 
@@ -595,8 +617,19 @@ impl Snapshot {
             .open(&s.meta_file.tmp_path)?;
         s.meta_file.file = Some(f);
         s.hold_tmp_files = true;
-
-        for cf_file in &mut s.cf_files {
+        // Partial files can only be resumed when they are written in
+        // plaintext; with encryption enabled the encrypter's stream state
+        // cannot be rebuilt in the middle of a file.
+        s.keep_partial_on_drop = s.mgr.encryption_key_manager.is_none();
+
+        // Resume from the cf files persisted by a previously interrupted
+        // receive of the same snapshot, if there was one. Files form a
+        // sequential stream, so everything after the first incomplete file
+        // starts over.
+        let mut resumable =
+            s.keep_partial_on_drop && s.mgr.recv_progress.lock().unwrap().contains_key(key);
+        let mut resume_pos = None;
+        for (cf_index, cf_file) in s.cf_files.iter_mut().enumerate() {
             if cf_file.size.is_empty() {
                 continue;
             }
@@ -607,9 +640,42 @@ impl Snapshot {
                     continue;
                 }
                 let file_path = Path::new(&tmp_file_paths[idx]);
+                if resumable
+                    && file_exists(file_path)
+                    && get_file_size(file_path)? <= cf_file.size[idx]
+                {
+                    let mut reader = File::open(file_path)?;
+                    let mut write_digest = crc32fast::Hasher::new();
+                    let mut written_size = 0;
+                    let mut buf = vec![0; IO_LIMITER_CHUNK_SIZE];
+                    loop {
+                        let n = reader.read(&mut buf)?;
+                        if n == 0 {
+                            break;
+                        }
+                        write_digest.update(&buf[..n]);
+                        written_size += n as u64;
+                    }
+                    let f = OpenOptions::new().append(true).open(file_path)?;
+                    cf_file.file_for_recving.push(CfFileForRecving {
+                        file: f,
+                        encrypter: None,
+                        written_size,
+                        write_digest,
+                    });
+                    s.recv_resume_offset += written_size;
+                    if written_size < cf_file.size[idx] {
+                        resumable = false;
+                        resume_pos.get_or_insert((cf_index, idx));
+                    }
+                    continue;
+                }
+                resumable = false;
+                resume_pos.get_or_insert((cf_index, idx));
                 let f = OpenOptions::new()
                     .write(true)
-                    .create_new(true)
+                    .create(true)
+                    .truncate(true)
                     .open(file_path)?;
                 cf_file.file_for_recving.push(CfFileForRecving {
                     file: f,
@@ -636,9 +702,25 @@ impl Snapshot {
                 }
             }
         }
+        match resume_pos {
+            Some((cf_index, cf_file_index)) => {
+                s.cf_index = cf_index;
+                s.cf_file_index = cf_file_index;
+            }
+            // Every file is already complete; all incoming bytes are dropped.
+            None => s.cf_index = s.cf_files.len(),
+        }
         Ok(s)
     }
 
+    /// Bytes persisted by a previously interrupted receive of this snapshot.
+    /// The incoming stream restarts from byte zero; this prefix is dropped
+    /// instead of being written to disk again, and the final checksum in
+    /// `save` still covers the resumed bytes.
+    pub fn resumed_offset(&self) -> u64 {
+        self.recv_resume_offset
+    }
+
     fn new_for_applying<T: Into<PathBuf>>(
         dir: T,
         key: &SnapKey,
@@ -875,30 +957,21 @@ impl Snapshot {
         }
 
         let (begin_key, end_key) = (enc_start_key(region), enc_end_key(region));
-        for (cf_enum, cf) in SNAPSHOT_CFS_ENUM_PAIR {
+        // Scan all CFs concurrently, each streaming to its own writer.
+        let cf_stats = snap_io::build_cf_files_parallel::<EK>(
+            &mut self.cf_files,
+            engine,
+            kv_snap,
+            &begin_key,
+            &end_key,
+            self.mgr
+                .get_actual_max_per_file_size(allow_multi_files_snapshot),
+            &self.mgr.limiter,
+            self.mgr.encryption_key_manager.clone(),
+        )?;
+        for ((cf_enum, cf), cf_stat) in SNAPSHOT_CFS_ENUM_PAIR.iter().zip(cf_stats) {
             self.switch_to_cf_file(cf)?;
             let cf_file = &mut self.cf_files[self.cf_index];
-            let cf_stat = if plain_file_used(cf_file.cf) {
-                snap_io::build_plain_cf_file::<EK>(
-                    cf_file,
-                    self.mgr.encryption_key_manager.as_ref(),
-                    kv_snap,
-                    &begin_key,
-                    &end_key,
-                )?
-            } else {
-                snap_io::build_sst_cf_file_list::<EK>(
-                    cf_file,
-                    engine,
-                    kv_snap,
-                    &begin_key,
-                    &end_key,
-                    self.mgr
-                        .get_actual_max_per_file_size(allow_multi_files_snapshot),
-                    &self.mgr.limiter,
-                    self.mgr.encryption_key_manager.clone(),
-                )?
-            };
             SNAPSHOT_LIMIT_GENERATE_BYTES.inc_by(cf_stat.total_size as u64);
             cf_file.kv_count = cf_stat.key_count as u64;
             if cf_file.kv_count > 0 {
@@ -1024,6 +1097,7 @@ impl Snapshot {
         if self.hold_tmp_files {
             delete_file_if_exist(&self.meta_file.tmp_path).unwrap();
         }
+        self.mgr.recv_progress.lock().unwrap().remove(&self.key);
     }
 
     // This is only used for v2 compatibility.
@@ -1133,7 +1207,11 @@ impl Snapshot {
 
         box_try!(self.validate(post_check));
 
-        let abort_checker = ApplyAbortChecker(options.abort);
+        let abort_checker = ApplyAbortChecker {
+            status: options.abort,
+            mgr: self.mgr.clone(),
+            region_id: self.key.region_id,
+        };
         let coprocessor_host = options.coprocessor_host;
         let region = options.region;
         let key_mgr = self.mgr.encryption_key_manager.as_ref();
@@ -1145,6 +1223,20 @@ impl Snapshot {
             let cf = cf_file.cf;
             if plain_file_used(cf_file.cf) {
                 let path = &cf_file.file_paths()[0];
+                if cf_file.size[0] >= PLAIN_FILE_INGEST_THRESHOLD {
+                    // Observers see the data through the sst hook in this mode.
+                    let _timer = INGEST_SST_DURATION_SECONDS.start_coarse_timer();
+                    snap_io::apply_plain_cf_file_as_sst(
+                        path,
+                        key_mgr,
+                        &abort_checker,
+                        &options.db,
+                        cf,
+                        &self.mgr.limiter,
+                    )?;
+                    coprocessor_host.post_apply_sst_from_snapshot(&region, cf, path);
+                    continue;
+                }
                 let batch_size = options.write_batch_size;
                 let cb = |kv: &[(Vec<u8>, Vec<u8>)]| {
                     coprocessor_host.post_apply_plain_kvs_from_snapshot(&region, cf, kv)
@@ -1156,6 +1248,8 @@ impl Snapshot {
                     &options.db,
                     cf,
                     batch_size,
+                    snap_io::APPLY_BATCH_SHRINK_CAPACITY,
+                    &self.mgr.limiter,
                     cb,
                 )?;
             } else {
@@ -1166,7 +1260,14 @@ impl Snapshot {
                     .iter()
                     .map(|s| s.as_str())
                     .collect::<Vec<&str>>();
-                snap_io::apply_sst_cf_file(clone_files.as_slice(), &options.db, cf)?;
+                snap_io::apply_sst_cf_file(
+                    clone_files.as_slice(),
+                    &options.db,
+                    cf,
+                    key_mgr.cloned(),
+                    &abort_checker,
+                    &self.mgr.limiter,
+                )?;
                 coprocessor_host.post_apply_sst_from_snapshot(&region, cf, path);
             }
         }
@@ -1271,6 +1372,7 @@ impl Snapshot {
         file_system::rename(&self.meta_file.tmp_path, &self.meta_file.path)?;
         sync_dir(&self.dir_path)?;
         self.hold_tmp_files = false;
+        self.mgr.recv_progress.lock().unwrap().remove(&self.key);
         Ok(())
     }
 
@@ -1279,11 +1381,22 @@ impl Snapshot {
     }
 }
 
-// To check whether a procedure about apply snapshot aborts or not.
-struct ApplyAbortChecker(Arc<AtomicUsize>);
+// To check whether a procedure about apply snapshot aborts or not, and to
+// block it while the region's apply is paused.
+struct ApplyAbortChecker {
+    status: Arc<AtomicUsize>,
+    mgr: SnapManagerCore,
+    region_id: u64,
+}
 impl snap_io::StaleDetector for ApplyAbortChecker {
     fn is_stale(&self) -> bool {
-        self.0.load(Ordering::Relaxed) == JOB_STATUS_CANCELLING
+        self.status.load(Ordering::Relaxed) == JOB_STATUS_CANCELLING
+    }
+
+    fn wait_if_paused(&self) {
+        while self.mgr.is_apply_paused(self.region_id) && !self.is_stale() {
+            thread::sleep(time::Duration::from_millis(APPLY_PAUSE_CHECK_INTERVAL_MS));
+        }
     }
 }
 
@@ -1326,7 +1439,18 @@ impl Write for Snapshot {
             return Ok(0);
         }
 
-        let (mut next_buf, mut written_bytes) = (buf, 0);
+        // Bytes already persisted by a previous receive attempt are dropped;
+        // their digest has been rebuilt from disk in `new_for_receiving`.
+        let mut skipped = 0;
+        if self.recv_resume_offset > 0 {
+            skipped = cmp::min(self.recv_resume_offset as usize, buf.len());
+            self.recv_resume_offset -= skipped as u64;
+            if skipped == buf.len() {
+                return Ok(skipped);
+            }
+        }
+
+        let (mut next_buf, mut written_bytes) = (&buf[skipped..], skipped);
         while self.cf_index < self.cf_files.len() {
             let cf_file = &mut self.cf_files[self.cf_index];
             if cf_file.size.is_empty() {
@@ -1408,6 +1532,23 @@ impl Drop for Snapshot {
     fn drop(&mut self) {
         // Cleanup if the snapshot is not built or received successfully.
         if self.hold_tmp_files {
+            let written: u64 = self
+                .cf_files
+                .iter()
+                .flat_map(|cf| cf.file_for_recving.iter().map(|f| f.written_size))
+                .sum();
+            if self.keep_partial_on_drop && written > 0 {
+                // Keep the partially received cf files so that a later attempt
+                // for the same key can resume from them; only the meta tmp
+                // file is removed to let a new receive take its place.
+                self.mgr
+                    .recv_progress
+                    .lock()
+                    .unwrap()
+                    .insert(self.key.clone(), written);
+                delete_file_if_exist(&self.meta_file.tmp_path).unwrap();
+                return;
+            }
             self.delete();
         }
     }
@@ -1440,6 +1581,11 @@ struct SnapManagerCore {
     max_per_file_size: Arc<AtomicU64>,
     enable_multi_snapshot_files: Arc<AtomicBool>,
     stats: Arc<Mutex<Vec<SnapshotStat>>>,
+    // Regions whose snapshot apply is paused, see `SnapManager::pause_snap_apply`.
+    apply_paused_regions: Arc<Mutex<HashSet<u64>>>,
+    // Bytes persisted by interrupted receives, keyed by snapshot. A new
+    // receive for a recorded key resumes from the partial cf files on disk.
+    recv_progress: Arc<Mutex<HashMap<SnapKey, u64>>>,
 }
 
 /// `SnapManagerCore` trace all current processing snapshots.
@@ -1634,6 +1780,10 @@ impl SnapManager {
         let _lock = self.core.registry.rl();
         let base = &self.core.base;
         let mut s = Snapshot::new_for_sending(base, key, &self.core)?;
+        // Verify the recorded checksums before the files leave this store, so
+        // that disk corruption after the build fails the send with a
+        // `Corrupted` error instead of poisoning the receiver.
+        s.validate(|_, _| Ok(()))?;
         let key_manager = match self.core.encryption_key_manager.as_ref() {
             Some(m) => m,
             None => return Ok(Box::new(s)),
@@ -1760,6 +1910,25 @@ impl SnapManager {
         self.core.limiter.set_speed_limit(bytes_per_sec);
     }
 
+    /// Pauses the snapshot apply of the given region. An ongoing apply blocks
+    /// between write batches (or SST files) until it is resumed or cancelled.
+    pub fn pause_snap_apply(&self, region_id: u64) {
+        self.core
+            .apply_paused_regions
+            .lock()
+            .unwrap()
+            .insert(region_id);
+    }
+
+    /// Resumes a snapshot apply paused by `pause_snap_apply`.
+    pub fn resume_snap_apply(&self, region_id: u64) {
+        self.core
+            .apply_paused_regions
+            .lock()
+            .unwrap()
+            .remove(&region_id);
+    }
+
     pub fn get_speed_limit(&self) -> f64 {
         self.core.limiter.speed_limit()
     }
@@ -1960,6 +2129,10 @@ impl SnapManagerCore {
         }
         u64::MAX
     }
+
+    fn is_apply_paused(&self, region_id: u64) -> bool {
+        self.apply_paused_regions.lock().unwrap().contains(&region_id)
+    }
 }
 
 #[derive(Clone, Default)]
@@ -2033,6 +2206,8 @@ impl SnapManagerBuilder {
                     self.enable_multi_snapshot_files,
                 )),
                 stats: Default::default(),
+                apply_paused_regions: Default::default(),
+                recv_progress: Default::default(),
             },
             max_total_size: Arc::new(AtomicU64::new(max_total_size)),
             tablet_snap_manager,
@@ -2519,6 +2694,8 @@ pub mod tests {
             max_per_file_size: Arc::new(AtomicU64::new(max_per_file_size)),
             enable_multi_snapshot_files: Arc::new(AtomicBool::new(true)),
             stats: Default::default(),
+            apply_paused_regions: Default::default(),
+            recv_progress: Default::default(),
         }
     }
 
@@ -2606,6 +2783,36 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_get_actual_max_per_file_size() {
+        let dir = Builder::new()
+            .prefix("test-max-per-file-size")
+            .tempdir()
+            .unwrap();
+        let core = create_manager_core(dir.path().to_str().unwrap(), 100);
+        // Chunked output requires both the caller and the config to allow it.
+        assert_eq!(core.get_actual_max_per_file_size(false), u64::MAX);
+        assert_eq!(core.get_actual_max_per_file_size(true), 100);
+        core.enable_multi_snapshot_files
+            .store(false, Ordering::Relaxed);
+        assert_eq!(core.get_actual_max_per_file_size(true), u64::MAX);
+    }
+
+    #[test]
+    fn test_pause_resume_snap_apply() {
+        let dir = Builder::new()
+            .prefix("test-pause-snap-apply")
+            .tempdir()
+            .unwrap();
+        let mgr = SnapManager::new(dir.path().to_str().unwrap());
+        assert!(!mgr.core.is_apply_paused(1));
+        mgr.pause_snap_apply(1);
+        assert!(mgr.core.is_apply_paused(1));
+        assert!(!mgr.core.is_apply_paused(2));
+        mgr.resume_snap_apply(1);
+        assert!(!mgr.core.is_apply_paused(1));
+    }
+
     #[test]
     fn test_display_path() {
         let dir = Builder::new()
@@ -2966,6 +3173,102 @@ pub mod tests {
         Snapshot::new_for_applying(dst_dir.path(), &key, &mgr_core).unwrap_err();
     }
 
+    #[test]
+    fn test_snap_corruption_on_sending() {
+        let region_id = 1;
+        let region = gen_test_region(region_id, 1, 1);
+        let db_dir = Builder::new()
+            .prefix("test-snap-corruption-sending-db")
+            .tempdir()
+            .unwrap();
+        let db: KvTestEngine = open_test_db(db_dir.path(), None, None).unwrap();
+        let snapshot = db.snapshot();
+
+        let dir = Builder::new()
+            .prefix("test-snap-corruption-sending")
+            .tempdir()
+            .unwrap();
+        let mgr = SnapManager::new(dir.path().to_str().unwrap());
+        mgr.init().unwrap();
+        let key = SnapKey::new(region_id, 1, 1);
+        let mut s1 = mgr.get_snapshot_for_building(&key).unwrap();
+        let _ = s1
+            .build(&db, &snapshot, &region, true, false, UnixSecs::now())
+            .unwrap();
+        mgr.get_snapshot_for_sending(&key).unwrap();
+
+        // Flip a byte in a cf file without changing its size. The recorded
+        // checksum no longer matches, so the snapshot must not be sent.
+        for p in file_system::read_dir(dir.path()).unwrap() {
+            let e = p.unwrap();
+            if e.file_name().into_string().unwrap().ends_with(META_FILE_SUFFIX) {
+                continue;
+            }
+            let mut data = file_system::read(e.path()).unwrap();
+            if data.is_empty() {
+                continue;
+            }
+            let mid = data.len() / 2;
+            data[mid] = !data[mid];
+            file_system::write(e.path(), &data).unwrap();
+        }
+
+        let err = mgr.get_snapshot_for_sending(&key).unwrap_err();
+        assert_eq!(err.error_code(), error_code::raftstore::SNAP_CORRUPTED);
+    }
+
+    #[test]
+    fn test_snap_resume_receiving() {
+        let region_id = 1;
+        let region = gen_test_region(region_id, 1, 1);
+        let db_dir = Builder::new()
+            .prefix("test-snap-resume-db")
+            .tempdir()
+            .unwrap();
+        let db: KvTestEngine = open_test_db_with_100keys(db_dir.path(), None, None).unwrap();
+        let snapshot = db.snapshot();
+
+        let src_dir = Builder::new()
+            .prefix("test-snap-resume-src")
+            .tempdir()
+            .unwrap();
+        let key = SnapKey::new(region_id, 1, 1);
+        let mgr_core = create_manager_core(src_dir.path().to_str().unwrap(), u64::MAX);
+        let mut s1 = Snapshot::new_for_building(src_dir.path(), &key, &mgr_core).unwrap();
+        let snap_data = s1
+            .build(&db, &snapshot, &region, true, false, UnixSecs::now())
+            .unwrap();
+        let meta = snap_data.get_meta().clone();
+
+        let mut sender = Snapshot::new_for_sending(src_dir.path(), &key, &mgr_core).unwrap();
+        let mut data = Vec::new();
+        sender.read_to_end(&mut data).unwrap();
+        assert_eq!(data.len() as u64, sender.total_size());
+
+        let dst_dir = Builder::new()
+            .prefix("test-snap-resume-dst")
+            .tempdir()
+            .unwrap();
+        let dst_core = create_manager_core(dst_dir.path().to_str().unwrap(), u64::MAX);
+
+        // The first attempt is interrupted half way through.
+        let half = data.len() / 2;
+        let mut s2 =
+            Snapshot::new_for_receiving(dst_dir.path(), &key, &dst_core, meta.clone()).unwrap();
+        assert_eq!(s2.resumed_offset(), 0);
+        s2.write_all(&data[..half]).unwrap();
+        drop(s2);
+
+        // The second attempt resumes from the persisted bytes, dropping the
+        // prefix of the restarted stream.
+        let mut s3 = Snapshot::new_for_receiving(dst_dir.path(), &key, &dst_core, meta).unwrap();
+        assert_eq!(s3.resumed_offset(), half as u64);
+        s3.write_all(&data).unwrap();
+        s3.save().unwrap();
+        assert!(s3.exists());
+        assert!(dst_core.recv_progress.lock().unwrap().is_empty());
+    }
+
     #[test]
     fn test_snap_corruption_on_meta_file() {
         let region_id = 1;
@@ -8,25 +8,31 @@ use std::{
     usize,
 };
 
-use encryption::{DataKeyManager, DecrypterReader, EncrypterWriter, Iv};
+use encryption::{DataKeyManager, EncrypterWriter, Iv};
 use engine_traits::{
-    CfName, Error as EngineError, Iterable, KvEngine, Mutable, SstCompressionType, SstReader,
-    SstWriter, SstWriterBuilder, WriteBatch,
+    iter_option, Error as EngineError, IngestExternalFileOptions, Iterable, Iterator,
+    KvEngine, Mutable, SstCompressionType, SstReader, SstWriter, SstWriterBuilder, WriteBatch,
 };
 use fail::fail_point;
+use futures::io::{AllowStdIo, AsyncRead};
 use kvproto::encryptionpb::EncryptionMethod;
 use tikv_util::{
-    box_try,
+    box_err, box_try,
     codec::bytes::{BytesEncoder, CompactBytesFromFileDecoder},
     debug, error, info,
     time::{Instant, Limiter},
 };
 
-use super::{CfFile, Error, IO_LIMITER_CHUNK_SIZE};
+use super::{plain_file_used, CfFile, Error, IO_LIMITER_CHUNK_SIZE, SST_FILE_SUFFIX};
 
 /// Used to check a procedure is stale or not.
 pub trait StaleDetector {
     fn is_stale(&self) -> bool;
+
+    /// Blocks while the procedure is paused. Called between write batches so
+    /// the store can deprioritize a long-running job, e.g. a snapshot apply
+    /// while foreground latency spikes, without aborting it.
+    fn wait_if_paused(&self) {}
 }
 
 #[derive(Clone, Copy, Default)]
@@ -49,6 +55,12 @@ where
     E: KvEngine,
 {
     let cf = cf_file.cf;
+    // Skip opening the file entirely when the range is provably empty, to
+    // avoid create+remove churn for empty CFs. If the emptiness can't be
+    // determined cheaply, fall back to the normal build path below.
+    if let Ok(true) = range_is_empty_cf(snap, cf, start_key, end_key) {
+        return Ok(BuildStatistics::default());
+    }
     let path = cf_file.path.join(cf_file.gen_tmp_file_name(0));
     let path = path.to_str().unwrap();
     let mut file = Some(box_try!(
@@ -104,6 +116,18 @@ where
     Ok(stats)
 }
 
+/// Check whether the given range of `cf` contains no keys by seeking the
+/// first key in the range, which is much cheaper than a full scan plus
+/// file create+remove when the range is empty.
+fn range_is_empty_cf<S>(snap: &S, cf: &str, start_key: &[u8], end_key: &[u8]) -> Result<bool, Error>
+where
+    S: Iterable,
+{
+    let iter_opt = iter_option(start_key, end_key, false);
+    let mut iter = box_try!(snap.iterator_opt(cf, iter_opt));
+    Ok(!box_try!(iter.seek(start_key)))
+}
+
 /// Build a snapshot file for the given column family in sst format.
 /// If there are no key-value pairs fetched, no files will be created at `path`,
 /// otherwise the file will be created and synchronized.
@@ -120,8 +144,49 @@ pub fn build_sst_cf_file_list<E>(
 where
     E: KvEngine,
 {
+    let files = build_sst_cf_files_sharded::<E>(
+        cf_file,
+        engine,
+        snap,
+        start_key,
+        end_key,
+        raw_size_per_file,
+        io_limiter,
+        key_mgr,
+    )?;
+    Ok(files.iter().fold(
+        BuildStatistics::default(),
+        |mut acc, (_, file_stats)| {
+            acc.key_count += file_stats.key_count;
+            acc.total_size += file_stats.total_size;
+            acc
+        },
+    ))
+}
+
+/// Build snapshot SST files for the given column family, rolling over to a
+/// new file once the current one reaches `target_file_size` of raw data.
+/// Returns the produced files together with their per-file statistics, in key
+/// order; keys never straddle two files. If there are no key-value pairs
+/// fetched, no files will be created at `path`.
+pub fn build_sst_cf_files_sharded<E>(
+    cf_file: &mut CfFile,
+    engine: &E,
+    snap: &E::Snapshot,
+    start_key: &[u8],
+    end_key: &[u8],
+    target_file_size: u64,
+    io_limiter: &Limiter,
+    key_mgr: Option<Arc<DataKeyManager>>,
+) -> Result<Vec<(String, BuildStatistics)>, Error>
+where
+    E: KvEngine,
+{
+    let raw_size_per_file = target_file_size;
     let cf = cf_file.cf;
     let mut stats = BuildStatistics::default();
+    let mut file_stats = BuildStatistics::default();
+    let mut files: Vec<(String, BuildStatistics)> = Vec::new();
     let mut remained_quota = 0;
     let mut file_id: usize = 0;
     let mut path = cf_file
@@ -193,6 +258,7 @@ where
             match result {
                 Ok(new_sst_writer) => {
                     let old_writer = sst_writer.replace(new_sst_writer);
+                    files.push((prev_path.clone(), std::mem::take(&mut file_stats)));
                     box_try!(finish_sst_writer(old_writer, prev_path, key_mgr.clone()));
                 }
                 Err(e) => {
@@ -211,6 +277,8 @@ where
 
         stats.key_count += 1;
         stats.total_size += entry_len;
+        file_stats.key_count += 1;
+        file_stats.total_size += entry_len;
         if let Err(e) = sst_writer.borrow_mut().put(key, value) {
             let io_error = io::Error::new(io::ErrorKind::Other, e);
             return Err(io_error.into());
@@ -219,6 +287,7 @@ where
         Ok(true)
     }));
     if stats.key_count > 0 {
+        files.push((path.clone(), file_stats));
         box_try!(finish_sst_writer(sst_writer.into_inner(), path, key_mgr));
         cf_file.add_file(file_id);
         info!(
@@ -233,11 +302,85 @@ where
     } else {
         box_try!(fs::remove_file(path));
     }
-    Ok(stats)
+    Ok(files)
+}
+
+/// Builds the snapshot files of all the given column families concurrently,
+/// scanning each cf on its own thread and streaming it to its own writer.
+/// The parallelism is bounded by the number of column families. Plain-format
+/// CFs go through `build_plain_cf_file`, the rest through
+/// `build_sst_cf_file_list`.
+///
+/// Returns per-cf statistics in the same order as `cf_files`.
+pub fn build_cf_files_parallel<E>(
+    cf_files: &mut [CfFile],
+    engine: &E,
+    snap: &E::Snapshot,
+    start_key: &[u8],
+    end_key: &[u8],
+    raw_size_per_file: u64,
+    io_limiter: &Limiter,
+    key_mgr: Option<Arc<DataKeyManager>>,
+) -> Result<Vec<BuildStatistics>, Error>
+where
+    E: KvEngine,
+{
+    std::thread::scope(|s| {
+        let mut handles = Vec::with_capacity(cf_files.len());
+        for cf_file in cf_files.iter_mut() {
+            let key_mgr = key_mgr.clone();
+            handles.push(s.spawn(move || {
+                if plain_file_used(cf_file.cf) {
+                    build_plain_cf_file::<E>(cf_file, key_mgr.as_ref(), snap, start_key, end_key)
+                } else {
+                    build_sst_cf_file_list::<E>(
+                        cf_file,
+                        engine,
+                        snap,
+                        start_key,
+                        end_key,
+                        raw_size_per_file,
+                        io_limiter,
+                        key_mgr,
+                    )
+                }
+            }));
+        }
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    })
+}
+
+/// Opens a built SST file for raw byte streaming, e.g. to send a snapshot
+/// file over the wire, throttled by `io_limiter` so the transport reads at
+/// a bounded rate. This decouples building SST files from transferring them.
+pub fn open_sst_for_read(
+    path: &str,
+    io_limiter: &Limiter,
+) -> io::Result<Box<dyn AsyncRead + Unpin>> {
+    let file = File::open(path)?;
+    Ok(Box::new(io_limiter.clone().limit(AllowStdIo::new(file))))
+}
+
+/// Entry capacity the reusable batch in `apply_plain_cf_file` may retain
+/// between writes. Without a bound, a single huge batch would inflate the
+/// retained memory for the rest of the apply.
+pub const APPLY_BATCH_SHRINK_CAPACITY: usize = 4096;
+
+/// Release the excess capacity of `batch` once it greatly exceeds
+/// `shrink_capacity`, the expected steady-state size.
+fn maybe_shrink_batch(batch: &mut Vec<(Vec<u8>, Vec<u8>)>, shrink_capacity: usize) {
+    if batch.capacity() > shrink_capacity {
+        batch.shrink_to(shrink_capacity);
+    }
 }
 
 /// Apply the given snapshot file into a column family. `callback` will be
-/// invoked after each batch of key value pairs written to db.
+/// invoked after each batch of key value pairs written to db. The writes are
+/// throttled by `io_limiter`, and `stale_detector` may pause the loop between
+/// batches to deprioritize the apply.
+///
+/// `shrink_capacity` bounds the entry capacity the internal batch may retain
+/// after a write; `APPLY_BATCH_SHRINK_CAPACITY` is a reasonable default.
 pub fn apply_plain_cf_file<E, F>(
     path: &str,
     key_mgr: Option<&Arc<DataKeyManager>>,
@@ -245,6 +388,8 @@ pub fn apply_plain_cf_file<E, F>(
     db: &E,
     cf: &str,
     batch_size: usize,
+    shrink_capacity: usize,
+    io_limiter: &Limiter,
     mut callback: F,
 ) -> Result<(), Error>
 where
@@ -273,6 +418,7 @@ where
     // times.
     let mut batch = Vec::with_capacity(1024);
     let mut batch_data_size = 0;
+    let mut remained_quota = 0;
 
     loop {
         if stale_detector.is_stale() {
@@ -286,16 +432,172 @@ where
             return Ok(());
         }
         let value = box_try!(decoder.decode_compact_bytes());
-        batch_data_size += key.len() + value.len();
+        let entry_len = key.len() + value.len();
+        while entry_len > remained_quota {
+            // It's possible to acquire more than necessary, but let it be.
+            io_limiter.blocking_consume(IO_LIMITER_CHUNK_SIZE);
+            remained_quota += IO_LIMITER_CHUNK_SIZE;
+        }
+        remained_quota -= entry_len;
+        batch_data_size += entry_len;
         batch.push((key, value));
         if batch_data_size >= batch_size {
+            stale_detector.wait_if_paused();
             box_try!(write_to_db(&mut batch));
             batch_data_size = 0;
+            maybe_shrink_batch(&mut batch, shrink_capacity);
+        }
+    }
+}
+
+/// Apply the given snapshot plain file by converting it into an SST and
+/// ingesting that, instead of replaying every key through a write batch.
+/// Large files avoid writing each key twice this way, once to the WAL and
+/// once to the memtable; for small files the ingestion overhead dominates
+/// and `apply_plain_cf_file` should be preferred.
+pub fn apply_plain_cf_file_as_sst<E>(
+    path: &str,
+    key_mgr: Option<&Arc<DataKeyManager>>,
+    stale_detector: &impl StaleDetector,
+    db: &E,
+    cf: &str,
+    io_limiter: &Limiter,
+) -> Result<(), Error>
+where
+    E: KvEngine,
+{
+    let file = box_try!(File::open(path));
+    let mut decoder = if let Some(key_mgr) = key_mgr {
+        let reader = get_decrypter_reader(path, key_mgr)?;
+        BufReader::new(reader)
+    } else {
+        BufReader::new(Box::new(file) as Box<dyn Read + Send>)
+    };
+
+    let sst_path = format!("{}{}", path, SST_FILE_SUFFIX);
+    let mut sst_writer = create_sst_file_writer::<E>(db, cf, &sst_path)?;
+    let mut key_count = 0;
+    let mut remained_quota = 0;
+    loop {
+        if stale_detector.is_stale() {
+            drop(sst_writer);
+            let _ = fs::remove_file(&sst_path);
+            return Err(Error::Abort);
+        }
+        // Plain files are written in key order, so the pairs can be fed to the
+        // sst writer as they are decoded.
+        let key = box_try!(decoder.decode_compact_bytes());
+        if key.is_empty() {
+            break;
+        }
+        let value = box_try!(decoder.decode_compact_bytes());
+        let entry_len = key.len() + value.len();
+        while entry_len > remained_quota {
+            // It's possible to acquire more than necessary, but let it be.
+            io_limiter.blocking_consume(IO_LIMITER_CHUNK_SIZE);
+            remained_quota += IO_LIMITER_CHUNK_SIZE;
+        }
+        remained_quota -= entry_len;
+        box_try!(sst_writer.put(&key, &value));
+        key_count += 1;
+    }
+    if key_count == 0 {
+        drop(sst_writer);
+        let _ = fs::remove_file(&sst_path);
+        return Ok(());
+    }
+    box_try!(sst_writer.finish());
+
+    stale_detector.wait_if_paused();
+    let mut opts = <E::IngestExternalFileOptions as IngestExternalFileOptions>::new();
+    opts.move_files(true);
+    opts.set_write_global_seqno(false);
+    opts.set_allow_write(true);
+    box_try!(db.ingest_external_file_cf_opt(cf, &[&sst_path], &opts));
+    Ok(())
+}
+
+/// Count the number of key-value records in a plain format CF file without
+/// materializing keys or values, skipping over their bytes using the
+/// compact-bytes length prefix. This is much cheaper than a full decode.
+/// Only unencrypted files are supported.
+pub fn count_plain_cf_records(path: &str) -> io::Result<usize> {
+    use tikv_util::codec::number;
+
+    fn decode_compact_len(reader: &mut impl io::BufRead) -> io::Result<u64> {
+        let mut var_data = Vec::with_capacity(number::MAX_VAR_I64_LEN);
+        while var_data.len() < number::MAX_VAR_U64_LEN {
+            let mut b = [0u8; 1];
+            reader.read_exact(&mut b)?;
+            var_data.push(b[0]);
+            if b[0] < 0x80 {
+                break;
+            }
+        }
+        number::decode_var_i64(&mut var_data.as_slice())
+            .map(|n| n as u64)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e)))
+    }
+
+    fn skip(reader: &mut impl io::BufRead, len: u64) -> io::Result<()> {
+        let copied = io::copy(&mut reader.by_ref().take(len), &mut io::sink())?;
+        if copied != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut count = 0;
+    loop {
+        let key_len = decode_compact_len(&mut reader)?;
+        if key_len == 0 {
+            // Hit the terminator.
+            return Ok(count);
         }
+        skip(&mut reader, key_len)?;
+        let value_len = decode_compact_len(&mut reader)?;
+        skip(&mut reader, value_len)?;
+        count += 1;
     }
 }
 
-pub fn apply_sst_cf_file<E>(files: &[&str], db: &E, cf: &str) -> Result<(), Error>
+/// Compare two plain format CF files for logical equality.
+///
+/// Both files are streamed through the same decode loop used by
+/// `apply_plain_cf_file` and their key-value pairs are compared in order,
+/// short-circuiting on the first difference. Only unencrypted files are
+/// supported.
+pub fn plain_cf_files_equal(a: &str, b: &str) -> io::Result<bool> {
+    let to_io_err = |e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", e));
+    let mut decoder_a = BufReader::new(File::open(a)?);
+    let mut decoder_b = BufReader::new(File::open(b)?);
+    loop {
+        let key_a = decoder_a.decode_compact_bytes().map_err(to_io_err)?;
+        let key_b = decoder_b.decode_compact_bytes().map_err(to_io_err)?;
+        if key_a != key_b {
+            return Ok(false);
+        }
+        if key_a.is_empty() {
+            // Both files hit the terminator with identical contents.
+            return Ok(true);
+        }
+        let value_a = decoder_a.decode_compact_bytes().map_err(to_io_err)?;
+        let value_b = decoder_b.decode_compact_bytes().map_err(to_io_err)?;
+        if value_a != value_b {
+            return Ok(false);
+        }
+    }
+}
+
+pub fn apply_sst_cf_file<E>(
+    files: &[&str],
+    db: &E,
+    cf: &str,
+    key_mgr: Option<Arc<DataKeyManager>>,
+    stale_detector: &impl StaleDetector,
+    io_limiter: &Limiter,
+) -> Result<(), Error>
 where
     E: KvEngine,
 {
@@ -305,11 +607,43 @@ where
             cf, files
         );
     }
-    box_try!(db.ingest_external_file_cf(cf, files));
+    // Reject corrupted files before ingestion; a bad snapshot must fail the
+    // apply instead of poisoning the engine.
+    for path in files {
+        let reader = box_try!(E::SstReader::open(path, key_mgr.clone()));
+        if let Err(e) = reader.verify_checksum() {
+            return Err(Error::Other(box_err!(
+                "corrupted sst {}: {:?}",
+                path,
+                e
+            )));
+        }
+    }
+    // Charge each file's size against the snapshot I/O budget before it is
+    // moved into the engine, and let the store pause the apply in between.
+    for path in files {
+        if stale_detector.is_stale() {
+            return Err(Error::Abort);
+        }
+        stale_detector.wait_if_paused();
+        let mut len = box_try!(fs::metadata(path)).len() as usize;
+        while len > 0 {
+            let chunk = std::cmp::min(IO_LIMITER_CHUNK_SIZE, len);
+            io_limiter.blocking_consume(chunk);
+            len -= chunk;
+        }
+    }
+    // Ingest without mutating the SSTs and without blocking foreground writes,
+    // so an apply doesn't stall the store.
+    let mut opts = <E::IngestExternalFileOptions as IngestExternalFileOptions>::new();
+    opts.move_files(true);
+    opts.set_write_global_seqno(false);
+    opts.set_allow_write(true);
+    box_try!(db.ingest_external_file_cf_opt(cf, files, &opts));
     Ok(())
 }
 
-fn create_sst_file_writer<E>(engine: &E, cf: CfName, path: &str) -> Result<E::SstWriter, Error>
+fn create_sst_file_writer<E>(engine: &E, cf: &str, path: &str) -> Result<E::SstWriter, Error>
 where
     E: KvEngine,
 {
@@ -321,24 +655,13 @@ where
     Ok(writer)
 }
 
-// TODO: Use DataKeyManager::open_file_for_read() instead.
 pub fn get_decrypter_reader(
     file: &str,
     encryption_key_manager: &DataKeyManager,
 ) -> Result<Box<dyn Read + Send>, Error> {
-    let enc_info = box_try!(encryption_key_manager.get_file(file));
-    let mthd = enc_info.method;
-    debug!(
-        "get_decrypter_reader gets enc_info for {:?}, method: {:?}",
-        file, mthd
-    );
-    if mthd == EncryptionMethod::Plaintext {
-        let f = box_try!(File::open(file));
-        return Ok(Box::new(f) as Box<dyn Read + Send>);
-    }
-    let iv = box_try!(Iv::from_slice(&enc_info.iv));
-    let f = box_try!(File::open(file));
-    let r = box_try!(DecrypterReader::new(f, mthd, &enc_info.key, iv));
+    // The decrypter is a passthrough for plaintext files, including files not
+    // tracked by the file dictionary.
+    let r = box_try!(encryption_key_manager.open_file_for_read(file));
     Ok(Box::new(r) as Box<dyn Read + Send>)
 }
 
@@ -347,7 +670,7 @@ mod tests {
     use std::{collections::HashMap, path::PathBuf};
 
     use engine_test::kv::KvTestEngine;
-    use engine_traits::CF_DEFAULT;
+    use engine_traits::{Peekable, CF_DEFAULT};
     use tempfile::Builder;
     use tikv_util::time::Limiter;
 
@@ -406,11 +729,21 @@ mod tests {
 
                     let detector = TestStaleDetector {};
                     let tmp_file_path = &cf_file.tmp_file_paths()[0];
-                    apply_plain_cf_file(tmp_file_path, None, &detector, &db1, cf, 16, |v| {
-                        v.iter()
-                            .cloned()
-                            .for_each(|pair| applied_keys.entry(cf).or_default().push(pair))
-                    })
+                    apply_plain_cf_file(
+                        tmp_file_path,
+                        None,
+                        &detector,
+                        &db1,
+                        cf,
+                        16,
+                        APPLY_BATCH_SHRINK_CAPACITY,
+                        &Limiter::new(f64::INFINITY),
+                        |v| {
+                            v.iter().cloned().for_each(|pair| {
+                                applied_keys.entry(cf).or_default().push(pair)
+                            })
+                        },
+                    )
                     .unwrap();
                 }
 
@@ -439,6 +772,171 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_apply_plain_cf_file_as_sst() {
+        let dir = Builder::new()
+            .prefix("test-snap-plain-as-sst-db")
+            .tempdir()
+            .unwrap();
+        let db: KvTestEngine = open_test_db_with_100keys(dir.path(), None, None).unwrap();
+        let snap = db.snapshot();
+        let snap_cf_dir = Builder::new()
+            .prefix("test-snap-plain-as-sst")
+            .tempdir()
+            .unwrap();
+        let mut cf_file = CfFile {
+            cf: CF_DEFAULT,
+            path: PathBuf::from(snap_cf_dir.path().to_str().unwrap()),
+            file_prefix: "test_plain_as_sst".to_string(),
+            file_suffix: SST_FILE_SUFFIX.to_string(),
+            ..Default::default()
+        };
+        let stats = build_plain_cf_file::<KvTestEngine>(
+            &mut cf_file,
+            None,
+            &snap,
+            &keys::data_key(b"a"),
+            &keys::data_end_key(b"z"),
+        )
+        .unwrap();
+        assert_eq!(stats.key_count, 100);
+
+        let dir1 = Builder::new()
+            .prefix("test-snap-plain-as-sst-db-apply")
+            .tempdir()
+            .unwrap();
+        let db1: KvTestEngine = open_test_empty_db(dir1.path(), None, None).unwrap();
+        apply_plain_cf_file_as_sst(
+            &cf_file.tmp_file_paths()[0],
+            None,
+            &TestStaleDetector {},
+            &db1,
+            CF_DEFAULT,
+            &Limiter::new(f64::INFINITY),
+        )
+        .unwrap();
+
+        let mut applied = 0;
+        db1.scan(
+            CF_DEFAULT,
+            &keys::data_key(b"a"),
+            &keys::data_end_key(b"z"),
+            true,
+            |k, v| {
+                assert_eq!(&*snap.get_value_cf(CF_DEFAULT, k).unwrap().unwrap(), v);
+                applied += 1;
+                Ok(true)
+            },
+        )
+        .unwrap();
+        assert_eq!(applied, 100);
+    }
+
+    #[test]
+    fn test_apply_batch_shrink() {
+        let mut batch: Vec<(Vec<u8>, Vec<u8>)> = Vec::with_capacity(1024);
+        // One huge batch inflates the capacity way beyond steady state.
+        batch.reserve(64 * 1024);
+        assert!(batch.capacity() >= 64 * 1024);
+        batch.clear();
+        maybe_shrink_batch(&mut batch, APPLY_BATCH_SHRINK_CAPACITY);
+        assert!(batch.capacity() <= APPLY_BATCH_SHRINK_CAPACITY);
+        // Batches within the threshold keep their capacity.
+        let mut batch: Vec<(Vec<u8>, Vec<u8>)> = Vec::with_capacity(1024);
+        maybe_shrink_batch(&mut batch, APPLY_BATCH_SHRINK_CAPACITY);
+        assert!(batch.capacity() >= 1024);
+    }
+
+    #[test]
+    fn test_build_plain_cf_file_skips_empty_range() {
+        let dir = Builder::new()
+            .prefix("test-snap-empty-cf-db")
+            .tempdir()
+            .unwrap();
+        let db: KvTestEngine = open_test_empty_db(dir.path(), None, None).unwrap();
+        let snap_cf_dir = Builder::new()
+            .prefix("test-snap-empty-cf")
+            .tempdir()
+            .unwrap();
+        let mut cf_file = CfFile {
+            cf: CF_DEFAULT,
+            path: PathBuf::from(snap_cf_dir.path().to_str().unwrap()),
+            file_prefix: "test_plain_sst".to_string(),
+            file_suffix: SST_FILE_SUFFIX.to_string(),
+            ..Default::default()
+        };
+        let stats = build_plain_cf_file::<KvTestEngine>(
+            &mut cf_file,
+            None,
+            &db.snapshot(),
+            &keys::data_key(b"a"),
+            &keys::data_end_key(b"z"),
+        )
+        .unwrap();
+        assert_eq!(stats.key_count, 0);
+        assert_eq!(cf_file.file_paths().len(), 0);
+        // The empty range is detected before any file is created, so the
+        // directory stays untouched.
+        assert_eq!(fs::read_dir(snap_cf_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_plain_cf_files_equal() {
+        let dir = Builder::new().prefix("test-plain-cf-eq").tempdir().unwrap();
+        let write_plain_file = |name: &str, kvs: &[(&[u8], &[u8])]| -> String {
+            let path = dir.path().join(name);
+            let mut f = File::create(&path).unwrap();
+            for (k, v) in kvs {
+                BytesEncoder::encode_compact_bytes(&mut f, k).unwrap();
+                BytesEncoder::encode_compact_bytes(&mut f, v).unwrap();
+            }
+            BytesEncoder::encode_compact_bytes(&mut f, b"").unwrap();
+            path.to_str().unwrap().to_string()
+        };
+
+        let kvs: &[(&[u8], &[u8])] = &[(b"k1", b"v1"), (b"k2", b"v2"), (b"k3", b"v3")];
+        let a = write_plain_file("a", kvs);
+        let b = write_plain_file("b", kvs);
+        assert!(plain_cf_files_equal(&a, &b).unwrap());
+
+        let kvs_diff: &[(&[u8], &[u8])] = &[(b"k1", b"v1"), (b"k2", b"v2x"), (b"k3", b"v3")];
+        let c = write_plain_file("c", kvs_diff);
+        assert!(!plain_cf_files_equal(&a, &c).unwrap());
+    }
+
+    #[test]
+    fn test_count_plain_cf_records() {
+        let dir = Builder::new().prefix("test-snap-count-db").tempdir().unwrap();
+        let db: KvTestEngine = open_test_db(dir.path(), None, None).unwrap();
+        let snap = db.snapshot();
+        for cf in SNAPSHOT_CFS {
+            let snap_cf_dir = Builder::new().prefix("test-snap-count").tempdir().unwrap();
+            let mut cf_file = CfFile {
+                cf,
+                path: PathBuf::from(snap_cf_dir.path().to_str().unwrap()),
+                file_prefix: "test_plain_sst".to_string(),
+                file_suffix: SST_FILE_SUFFIX.to_string(),
+                ..Default::default()
+            };
+            let stats = build_plain_cf_file::<KvTestEngine>(
+                &mut cf_file,
+                None,
+                &snap,
+                &keys::data_key(b"a"),
+                &keys::data_end_key(b"z"),
+            )
+            .unwrap();
+            if stats.key_count == 0 {
+                continue;
+            }
+            let tmp_file_path = &cf_file.tmp_file_paths()[0];
+            assert_eq!(
+                count_plain_cf_records(tmp_file_path).unwrap(),
+                stats.key_count
+            );
+        }
+    }
+
     #[test]
     fn test_cf_build_and_apply_sst_files() {
         let db_creaters = &[open_test_empty_db, open_test_db_with_100keys];
@@ -492,16 +990,201 @@ mod tests {
                         .prefix("test-snap-cf-db-apply")
                         .tempdir()
                         .unwrap();
+                    let key_mgr = db_opt.as_ref().and_then(|opt| opt.get_key_manager());
                     let db1: KvTestEngine = open_test_empty_db(dir1.path(), db_opt, None).unwrap();
                     let tmp_file_paths = cf_file.tmp_file_paths();
                     let tmp_file_paths = tmp_file_paths
                         .iter()
                         .map(|s| s.as_str())
                         .collect::<Vec<&str>>();
-                    apply_sst_cf_file(&tmp_file_paths, &db1, CF_DEFAULT).unwrap();
+                    apply_sst_cf_file(
+                        &tmp_file_paths,
+                        &db1,
+                        CF_DEFAULT,
+                        key_mgr,
+                        &TestStaleDetector {},
+                        &Limiter::new(f64::INFINITY),
+                    )
+                    .unwrap();
                     assert_eq_db(&db, &db1);
                 }
             }
         }
     }
+
+    #[test]
+    fn test_apply_sst_cf_file_reject_corrupted() {
+        let limiter = Limiter::new(f64::INFINITY);
+        let dir = Builder::new().prefix("test-snap-corrupt-db").tempdir().unwrap();
+        let db = open_test_db_with_100keys(dir.path(), None, None).unwrap();
+        let snap_cf_dir = Builder::new().prefix("test-snap-corrupt").tempdir().unwrap();
+        let mut cf_file = CfFile {
+            cf: CF_DEFAULT,
+            path: PathBuf::from(snap_cf_dir.path().to_str().unwrap()),
+            file_prefix: "test_sst".to_string(),
+            file_suffix: SST_FILE_SUFFIX.to_string(),
+            ..Default::default()
+        };
+        let stats = build_sst_cf_file_list::<KvTestEngine>(
+            &mut cf_file,
+            &db,
+            &db.snapshot(),
+            &keys::data_key(b"a"),
+            &keys::data_key(b"z"),
+            u64::MAX,
+            &limiter,
+            None,
+        )
+        .unwrap();
+        assert!(stats.key_count > 0);
+
+        // Flip a byte in the middle of the file to corrupt a data block.
+        let tmp_file_paths = cf_file.tmp_file_paths();
+        let path = &tmp_file_paths[0];
+        let mut data = fs::read(path).unwrap();
+        let mid = data.len() / 2;
+        data[mid] = !data[mid];
+        fs::write(path, &data).unwrap();
+
+        let dir1 = Builder::new()
+            .prefix("test-snap-corrupt-db-apply")
+            .tempdir()
+            .unwrap();
+        let db1: KvTestEngine = open_test_empty_db(dir1.path(), None, None).unwrap();
+        apply_sst_cf_file(
+            &[path.as_str()],
+            &db1,
+            CF_DEFAULT,
+            None,
+            &TestStaleDetector {},
+            &limiter,
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_build_sst_cf_files_sharded_stats() {
+        let limiter = Limiter::new(f64::INFINITY);
+        let dir = Builder::new().prefix("test-snap-cf-db").tempdir().unwrap();
+        let db = open_test_db_with_100keys(dir.path(), None, None).unwrap();
+        let snap_cf_dir = Builder::new().prefix("test-snap-cf").tempdir().unwrap();
+        let mut cf_file = CfFile {
+            cf: CF_DEFAULT,
+            path: PathBuf::from(snap_cf_dir.path().to_str().unwrap()),
+            file_prefix: "test_sst".to_string(),
+            file_suffix: SST_FILE_SUFFIX.to_string(),
+            ..Default::default()
+        };
+        let files = build_sst_cf_files_sharded::<KvTestEngine>(
+            &mut cf_file,
+            &db,
+            &db.snapshot(),
+            &keys::data_key(b"a"),
+            &keys::data_key(b"z"),
+            100,
+            &limiter,
+            None,
+        )
+        .unwrap();
+        assert!(files.len() > 1);
+        assert_eq!(files.len(), cf_file.tmp_file_paths().len());
+        let mut key_count = 0;
+        let mut total_size = 0;
+        for ((path, file_stats), tmp_file_path) in files.iter().zip(cf_file.tmp_file_paths()) {
+            assert_eq!(path, &tmp_file_path);
+            assert!(file_stats.key_count > 0);
+            // Keys never straddle two files, so each shard stays within the
+            // target size plus one entry.
+            assert!(file_stats.total_size <= 100 + file_stats.total_size / file_stats.key_count);
+            key_count += file_stats.key_count;
+            total_size += file_stats.total_size;
+        }
+        assert_eq!(key_count, 100);
+        assert!(total_size > 0);
+    }
+
+    #[test]
+    fn test_build_cf_files_parallel() {
+        let limiter = Limiter::new(f64::INFINITY);
+        let dir = Builder::new().prefix("test-snap-cf-db").tempdir().unwrap();
+        let db: KvTestEngine = open_test_db(dir.path(), None, None).unwrap();
+        let snap = db.snapshot();
+        let snap_cf_dir = Builder::new().prefix("test-snap-cf").tempdir().unwrap();
+        let mut cf_files = Vec::with_capacity(SNAPSHOT_CFS.len());
+        for cf in SNAPSHOT_CFS {
+            cf_files.push(CfFile {
+                cf,
+                path: PathBuf::from(snap_cf_dir.path().to_str().unwrap()),
+                file_prefix: format!("test_parallel_{}", cf),
+                file_suffix: SST_FILE_SUFFIX.to_string(),
+                ..Default::default()
+            });
+        }
+        let stats = build_cf_files_parallel::<KvTestEngine>(
+            &mut cf_files,
+            &db,
+            &snap,
+            &keys::data_key(b"a"),
+            &keys::data_end_key(b"z"),
+            u64::MAX,
+            &limiter,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(stats.len(), SNAPSHOT_CFS.len());
+        for (cf_file, stat) in cf_files.iter().zip(&stats) {
+            let mut expected = 0;
+            snap.scan(
+                cf_file.cf,
+                &keys::data_key(b"a"),
+                &keys::data_end_key(b"z"),
+                false,
+                |_, _| {
+                    expected += 1;
+                    Ok(true)
+                },
+            )
+            .unwrap();
+            assert_eq!(stat.key_count, expected);
+            assert_eq!(cf_file.tmp_file_paths().len(), usize::from(expected > 0));
+        }
+    }
+
+    #[test]
+    fn test_open_sst_for_read() {
+        let limiter = Limiter::new(f64::INFINITY);
+        let dir = Builder::new().prefix("test-snap-cf-db").tempdir().unwrap();
+        let db = open_test_db_with_100keys(dir.path(), None, None).unwrap();
+        let snap_cf_dir = Builder::new().prefix("test-snap-cf").tempdir().unwrap();
+        let mut cf_file = CfFile {
+            cf: CF_DEFAULT,
+            path: PathBuf::from(snap_cf_dir.path().to_str().unwrap()),
+            file_prefix: "test_sst".to_string(),
+            file_suffix: SST_FILE_SUFFIX.to_string(),
+            ..Default::default()
+        };
+        build_sst_cf_file_list::<KvTestEngine>(
+            &mut cf_file,
+            &db,
+            &db.snapshot(),
+            &keys::data_key(b"a"),
+            &keys::data_key(b"z"),
+            u64::MAX,
+            &limiter,
+            None,
+        )
+        .unwrap();
+        let path = &cf_file.tmp_file_paths()[0];
+
+        let mut reader = open_sst_for_read(path, &limiter).unwrap();
+        let mut buf = Vec::new();
+        futures::executor::block_on(futures::io::AsyncReadExt::read_to_end(
+            &mut reader,
+            &mut buf,
+        ))
+        .unwrap();
+        assert!(!buf.is_empty());
+        assert_eq!(buf, fs::read(path).unwrap());
+    }
 }
@@ -393,6 +393,23 @@ mod tests {
         assert_eq!(GLOBAL_MEM_SIZE.get(), 0);
     }
 
+    #[test]
+    fn test_is_writable() {
+        let mut locks = PeerPessimisticLocks::default();
+        assert!(locks.is_writable());
+        for status in [
+            LocksStatus::TransferringLeader,
+            LocksStatus::MergingRegion,
+            LocksStatus::NotLeader,
+            LocksStatus::IsInFlashback,
+        ] {
+            locks.status = status;
+            assert!(!locks.is_writable());
+        }
+        locks.status = LocksStatus::Normal;
+        assert!(locks.is_writable());
+    }
+
     #[test]
     fn test_insert_checking_memory_limit() {
         let _guard = TEST_MUTEX.lock().unwrap();
@@ -509,3 +509,154 @@ pub fn demote_failed_voters_request(
     req.set_admin_request(new_change_peer_v2_request(change_peer_reqs));
     Some(req)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    // Records how far the drop-driven report state machine advances.
+    #[derive(Default)]
+    struct MockHandle {
+        wait_apply: AtomicUsize,
+        exit_force_leader: AtomicUsize,
+        fill_out_report: AtomicUsize,
+        reports: Mutex<Vec<StoreReport>>,
+    }
+
+    impl UnsafeRecoveryHandle for MockHandle {
+        fn send_enter_force_leader(
+            &self,
+            _region_id: u64,
+            _syncer: UnsafeRecoveryForceLeaderSyncer,
+            _failed_stores: HashSet<u64>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        fn broadcast_exit_force_leader(&self) {
+            self.exit_force_leader.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn send_create_peer(
+            &self,
+            _region: metapb::Region,
+            _syncer: UnsafeRecoveryExecutePlanSyncer,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        fn send_destroy_peer(
+            &self,
+            _region_id: u64,
+            _syncer: UnsafeRecoveryExecutePlanSyncer,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        fn send_demote_peers(
+            &self,
+            _region_id: u64,
+            _failed_voters: Vec<metapb::Peer>,
+            _syncer: UnsafeRecoveryExecutePlanSyncer,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        fn broadcast_wait_apply(&self, syncer: UnsafeRecoveryWaitApplySyncer) {
+            self.wait_apply.fetch_add(1, Ordering::SeqCst);
+            // Dropping the syncer simulates all peers finishing wait apply.
+            drop(syncer);
+        }
+
+        fn broadcast_fill_out_report(&self, syncer: UnsafeRecoveryFillOutReportSyncer) {
+            self.fill_out_report.fetch_add(1, Ordering::SeqCst);
+            drop(syncer);
+        }
+
+        fn send_report(&self, report: StoreReport) -> Result<()> {
+            self.reports.lock().unwrap().push(report);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_execute_plan_syncer_reports_on_drop() {
+        let handle = Arc::new(MockHandle::default());
+        let syncer = UnsafeRecoveryExecutePlanSyncer::new(7, handle.clone());
+        let cloned = syncer.clone();
+        drop(syncer);
+        // The last reference drives the whole report chain.
+        assert_eq!(handle.wait_apply.load(Ordering::SeqCst), 0);
+        drop(cloned);
+        assert_eq!(handle.wait_apply.load(Ordering::SeqCst), 1);
+        assert_eq!(handle.exit_force_leader.load(Ordering::SeqCst), 1);
+        assert_eq!(handle.fill_out_report.load(Ordering::SeqCst), 1);
+        let reports = handle.reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].get_step(), 7);
+    }
+
+    #[test]
+    fn test_execute_plan_syncer_abort() {
+        let handle = Arc::new(MockHandle::default());
+        let syncer = UnsafeRecoveryExecutePlanSyncer::new(7, handle.clone());
+        syncer.abort();
+        drop(syncer);
+        assert_eq!(handle.wait_apply.load(Ordering::SeqCst), 0);
+        assert!(handle.reports.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_force_leader_syncer_starts_report_without_exiting() {
+        let handle = Arc::new(MockHandle::default());
+        let syncer = UnsafeRecoveryForceLeaderSyncer::new(1, handle.clone());
+        drop(syncer);
+        assert_eq!(handle.wait_apply.load(Ordering::SeqCst), 1);
+        // Force leader state must be kept for the plan execution phase.
+        assert_eq!(handle.exit_force_leader.load(Ordering::SeqCst), 0);
+        assert_eq!(handle.reports.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_demote_failed_voters_request() {
+        let mut region = metapb::Region::default();
+        region.set_id(1);
+        let mut voter = metapb::Peer::default();
+        voter.set_id(2);
+        voter.set_store_id(2);
+        let mut failed_voter = metapb::Peer::default();
+        failed_voter.set_id(3);
+        failed_voter.set_store_id(3);
+        region.mut_peers().push(voter.clone());
+        region.mut_peers().push(failed_voter.clone());
+
+        let req =
+            demote_failed_voters_request(&region, &voter, vec![failed_voter.clone()]).unwrap();
+        let changes = req.get_admin_request().get_change_peer_v2().get_changes();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].get_change_type(),
+            ConfChangeType::AddLearnerNode
+        );
+        assert_eq!(changes[0].get_peer().get_id(), 3);
+        assert_eq!(
+            changes[0].get_peer().get_role(),
+            metapb::PeerRole::Learner
+        );
+
+        // A learner proposing the demotion promotes itself to voter.
+        let mut learner = voter.clone();
+        learner.set_role(metapb::PeerRole::Learner);
+        region.mut_peers()[0].set_role(metapb::PeerRole::Learner);
+        let req = demote_failed_voters_request(&region, &learner, vec![failed_voter]).unwrap();
+        let changes = req.get_admin_request().get_change_peer_v2().get_changes();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[1].get_change_type(), ConfChangeType::AddNode);
+        assert_eq!(changes[1].get_peer().get_id(), 2);
+
+        // Nothing to demote and nothing to promote.
+        assert!(demote_failed_voters_request(&region, &voter, vec![]).is_none());
+    }
+}
@@ -15,7 +15,7 @@ use std::{
 };
 
 use collections::HashSet;
-use engine_traits::KvEngine;
+use engine_traits::{KvEngine, Range};
 use kvproto::{
     kvrpcpb::{self, KeyRange, LeaderInfo},
     metapb::{self, Peer, PeerRole, Region, RegionEpoch},
@@ -462,6 +462,39 @@ pub fn is_region_initialized(r: &metapb::Region) -> bool {
     !r.get_peers().is_empty()
 }
 
+/// A pair of data keys covering the whole range of a region, borrowable as an
+/// engine `Range`. It applies the data-key prefix to both boundaries, mapping
+/// the empty end key of the right-most region to `DATA_MAX_KEY` so the range
+/// stays bounded within the data prefix.
+#[derive(Clone, Debug)]
+pub struct RegionDataRange {
+    start_key: Vec<u8>,
+    end_key: Vec<u8>,
+}
+
+impl RegionDataRange {
+    pub fn as_range(&self) -> Range<'_> {
+        Range::new(&self.start_key, &self.end_key)
+    }
+
+    pub fn start_key(&self) -> &[u8] {
+        &self.start_key
+    }
+
+    pub fn end_key(&self) -> &[u8] {
+        &self.end_key
+    }
+}
+
+/// Converts a region's boundaries into the data-key range it covers.
+#[inline]
+pub fn region_data_range(region: &metapb::Region) -> RegionDataRange {
+    RegionDataRange {
+        start_key: keys::data_key(region.get_start_key()),
+        end_key: keys::data_end_key(region.get_end_key()),
+    }
+}
+
 /// Lease records an expired time, for examining the current moment is in lease
 /// or not. It's dedicated to the Raft leader lease mechanism, contains either
 /// state of
@@ -1883,6 +1916,26 @@ mod tests {
     use super::*;
     use crate::store::peer_storage;
 
+    #[test]
+    fn test_region_data_range() {
+        let mut region = metapb::Region::default();
+        region.set_start_key(b"a".to_vec());
+        region.set_end_key(b"z".to_vec());
+        let r = region_data_range(&region);
+        assert_eq!(r.start_key(), keys::data_key(b"a").as_slice());
+        assert_eq!(r.end_key(), keys::data_key(b"z").as_slice());
+        let range = r.as_range();
+        assert_eq!(range.start_key, r.start_key());
+        assert_eq!(range.end_key, r.end_key());
+
+        // An empty end key means the region is unbounded on the right, which
+        // maps to the end of the data prefix.
+        region.set_end_key(b"".to_vec());
+        let r = region_data_range(&region);
+        assert_eq!(r.start_key(), keys::data_key(b"a").as_slice());
+        assert_eq!(r.end_key(), keys::DATA_MAX_KEY);
+    }
+
     #[test]
     fn test_lease() {
         #[inline]
@@ -2121,6 +2174,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_confchange_kind() {
+        assert_eq!(
+            ConfChangeKind::confchange_kind(0),
+            ConfChangeKind::LeaveJoint
+        );
+        assert_eq!(ConfChangeKind::confchange_kind(1), ConfChangeKind::Simple);
+        for num in 2..5 {
+            assert_eq!(
+                ConfChangeKind::confchange_kind(num),
+                ConfChangeKind::EnterJoint
+            );
+        }
+    }
+
+    #[test]
+    fn test_legacy_changepeer_to_confchange() {
+        let mut req = ChangePeerRequest::default();
+        req.set_change_type(eraftpb::ConfChangeType::AddLearnerNode);
+        req.mut_peer().set_id(5);
+
+        let cc = (&req).to_confchange(b"ctx".to_vec());
+        assert_eq!(
+            cc.get_change_type(),
+            eraftpb::ConfChangeType::AddLearnerNode
+        );
+        assert_eq!(cc.get_node_id(), 5);
+        assert_eq!(cc.get_context(), b"ctx");
+        // A legacy request always carries exactly one change.
+        assert_eq!((&req).get_change_peers(), vec![req.clone()]);
+    }
+
     #[test]
     fn test_first_vote_msg() {
         let tbl = vec![
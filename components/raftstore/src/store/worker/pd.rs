@@ -3,6 +3,7 @@
 use std::{
     cmp,
     cmp::Ordering as CmpOrdering,
+    collections::VecDeque,
     fmt::{self, Display, Formatter},
     io, mem,
     sync::{
@@ -1011,6 +1012,7 @@ where
     scheduler: Scheduler<Task<EK, ER>>,
     stats_monitor: StatsMonitor<WrappedScheduler<EK, ER>>,
     store_heartbeat_interval: Duration,
+    transfer_leader_throttle: Arc<TransferLeaderThrottle>,
 
     // region_id -> total_cpu_time_ms (since last region heartbeat)
     region_cpu_records: HashMap<u64, u32>,
@@ -1077,6 +1079,7 @@ where
             start_ts: UnixSecs::now(),
             scheduler,
             store_heartbeat_interval,
+            transfer_leader_throttle: Arc::new(TransferLeaderThrottle::new(cfg)),
             stats_monitor,
             region_cpu_records: HashMap::default(),
             concurrency_manager,
@@ -1622,6 +1625,7 @@ where
     fn schedule_heartbeat_receiver(&mut self) {
         let router = self.router.clone();
         let store_id = self.store_id;
+        let transfer_leader_throttle = self.transfer_leader_throttle.clone();
 
         let fut = self.pd_client
             .handle_region_heartbeat_response(self.store_id, move |mut resp| {
@@ -1660,11 +1664,24 @@ where
                     let req = new_change_peer_v2_request(change_peer_v2.take_changes().into());
                     send_admin_request(&router, region_id, epoch, peer, req, Callback::None, Default::default());
                 } else if resp.has_transfer_leader() {
+                    let mut transfer_leader = resp.take_transfer_leader();
+                    if !transfer_leader_throttle.try_start() {
+                        // PD will schedule the transfer again through later
+                        // heartbeats if it's still needed.
+                        PD_HEARTBEAT_COUNTER_VEC
+                            .with_label_values(&["transfer leader throttled"])
+                            .inc();
+                        info!(
+                            "transfer leader throttled";
+                            "region_id" => region_id,
+                            "to_peer" => ?transfer_leader.get_peer(),
+                        );
+                        return;
+                    }
                     PD_HEARTBEAT_COUNTER_VEC
                         .with_label_values(&["transfer leader"])
                         .inc();
 
-                    let mut transfer_leader = resp.take_transfer_leader();
                     info!(
                         "try to transfer leader";
                         "region_id" => region_id,
@@ -2480,6 +2497,46 @@ fn new_batch_split_region_request(
     req
 }
 
+/// Throttles leader transfers scheduled by PD, so that bursts of transfers
+/// during rolling restarts don't cause election storms on the store.
+struct TransferLeaderThrottle {
+    max_per_interval: usize,
+    min_interval: Duration,
+    history: Mutex<VecDeque<Instant>>,
+}
+
+impl TransferLeaderThrottle {
+    fn new(cfg: &Config) -> TransferLeaderThrottle {
+        TransferLeaderThrottle {
+            max_per_interval: cfg.max_pd_transfer_leaders_per_interval as usize,
+            min_interval: cfg.pd_transfer_leader_min_interval.0,
+            history: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Checks whether a new transfer is allowed to start and records it if
+    /// it is.
+    fn try_start(&self) -> bool {
+        if self.max_per_interval == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        let mut history = self.history.lock().unwrap();
+        while let Some(t) = history.front() {
+            if now.saturating_duration_since(*t) >= self.min_interval {
+                history.pop_front();
+            } else {
+                break;
+            }
+        }
+        if history.len() >= self.max_per_interval {
+            return false;
+        }
+        history.push_back(now);
+        true
+    }
+}
+
 fn new_transfer_leader_request(peer: metapb::Peer, peers: Vec<metapb::Peer>) -> AdminRequest {
     let mut req = AdminRequest::default();
     req.set_cmd_type(AdminCmdType::TransferLeader);
@@ -2674,6 +2731,7 @@ mod tests {
 
     use kvproto::{kvrpcpb, pdpb::QueryKind};
     use pd_client::{new_bucket_stats, BucketMeta};
+    use tikv_util::config::ReadableDuration;
 
     use super::*;
 
@@ -2992,4 +3050,44 @@ mod tests {
         assert_eq!(used, 111);
         assert_eq!(avail, 333);
     }
+
+    #[test]
+    fn test_transfer_leader_throttle() {
+        let mut cfg = Config::default();
+        // Disabled by default.
+        let throttle = TransferLeaderThrottle::new(&cfg);
+        for _ in 0..100 {
+            assert!(throttle.try_start());
+        }
+
+        cfg.max_pd_transfer_leaders_per_interval = 2;
+        cfg.pd_transfer_leader_min_interval = ReadableDuration::millis(100);
+        let throttle = TransferLeaderThrottle::new(&cfg);
+        assert!(throttle.try_start());
+        assert!(throttle.try_start());
+        // The limit within the interval is reached.
+        assert!(!throttle.try_start());
+        // New transfers are allowed once the interval has elapsed.
+        sleep(Duration::from_millis(200));
+        assert!(throttle.try_start());
+    }
+
+    #[test]
+    fn test_new_batch_switch_witness() {
+        let mut switches = Vec::new();
+        for (peer_id, is_witness) in [(1, true), (2, false)] {
+            let mut sw = pdpb::SwitchWitness::default();
+            sw.set_peer_id(peer_id);
+            sw.set_is_witness(is_witness);
+            switches.push(sw);
+        }
+        let req = new_batch_switch_witness(switches);
+        assert_eq!(req.get_cmd_type(), AdminCmdType::BatchSwitchWitness);
+        let switch_reqs = req.get_switch_witnesses().get_switch_witnesses();
+        assert_eq!(switch_reqs.len(), 2);
+        assert_eq!(switch_reqs[0].get_peer_id(), 1);
+        assert!(switch_reqs[0].get_is_witness());
+        assert_eq!(switch_reqs[1].get_peer_id(), 2);
+        assert!(!switch_reqs[1].get_is_witness());
+    }
 }
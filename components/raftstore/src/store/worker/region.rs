@@ -714,11 +714,13 @@ where
     fn delete_all_in_range(&self, ranges: &[Range<'_>]) -> Result<()> {
         let wopts = WriteOptions::default();
         for cf in self.engine.cf_names() {
-            // CF_LOCK usually contains fewer keys than other CFs, so we delete them by key.
-            let strategy = if cf == CF_LOCK {
+            // `Auto` deletes ranges with few keys (e.g. in CF_LOCK) by key and
+            // the rest by range, judged from the engine's own stats.
+            let strategy = if self.use_delete_range {
+                DeleteStrategy::Auto
+            } else if cf == CF_LOCK {
+                // CF_LOCK usually contains fewer keys than other CFs, so we delete them by key.
                 DeleteStrategy::DeleteByKey
-            } else if self.use_delete_range {
-                DeleteStrategy::DeleteByRange
             } else {
                 DeleteStrategy::DeleteByWriter {
                     sst_path: self.mgr.get_temp_path_for_ingest(),
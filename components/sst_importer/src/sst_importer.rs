@@ -2348,6 +2348,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_verify_checksum_corrupted_sst() {
+        // creates a sample SST file.
+        let (_ext_sst_dir, backend, meta) = create_sample_external_sst_file().unwrap();
+
+        // performs the download.
+        let importer_dir = tempfile::tempdir().unwrap();
+        let cfg = Config::default();
+        let importer =
+            SstImporter::<TestEngine>::new(&cfg, &importer_dir, None, ApiVersion::V1, false)
+                .unwrap();
+        let db = create_sst_test_engine().unwrap();
+
+        importer
+            .download(
+                &meta,
+                &backend,
+                "sample.sst",
+                &RewriteRule::default(),
+                None,
+                Limiter::new(f64::INFINITY),
+                db,
+            )
+            .unwrap()
+            .unwrap();
+
+        // The freshly downloaded file passes verification.
+        importer.verify_checksum(&[meta.clone()]).unwrap();
+
+        // Flip a byte in the middle of the saved file, the block checksum
+        // must catch it before ingestion.
+        let sst_file_path = importer.dir.join_for_read(&meta).unwrap().save;
+        let mut data = file_system::read(&sst_file_path).unwrap();
+        let mid = data.len() / 2;
+        data[mid] = !data[mid];
+        file_system::write(&sst_file_path, &data).unwrap();
+        importer.verify_checksum(&[meta]).unwrap_err();
+    }
+
     #[test]
     fn test_download_sst_no_key_rewrite_with_encrypted() {
         // creates a sample SST file.
@@ -61,6 +61,7 @@ use tikv_util::{
     box_err, box_try, debug, error,
     future::block_on_timeout,
     safe_panic,
+    sys::disk::{self, DiskUsage},
     thread_group::GroupProperties,
     time::{Instant, ThreadReadId},
     warn,
@@ -1260,6 +1261,25 @@ impl<T: Simulator<EK>, EK: KvEngine> Cluster<T, EK> {
         }
     }
 
+    /// Simulates a disk failure on the given store, e.g. `AlreadyFull` makes
+    /// raftstore reject every proposal that doesn't carry an allowed
+    /// `DiskFullOpt` as if the disk has no space left.
+    pub fn set_disk_usage(&self, store_id: u64, usage: DiskUsage) {
+        disk::set_disk_status_of_store(store_id, usage);
+    }
+
+    /// Recovers the simulated disk failure on the given store.
+    pub fn clear_disk_usage(&self, store_id: u64) {
+        disk::clear_disk_status_of_store(store_id);
+    }
+
+    /// Advances the monotonic clocks of every node by `duration`, so leases
+    /// and timeouts based on them expire without actually sleeping. Note that
+    /// the clocks are process-wide and can't be turned back.
+    pub fn advance_clock(&self, duration: Duration) {
+        tikv_util::time::advance_monotonic_clock(duration);
+    }
+
     // Get region when the `filter` returns true.
     pub fn get_region_with<F>(&self, key: &[u8], filter: F) -> metapb::Region
     where
@@ -1875,6 +1895,11 @@ impl<T: Simulator<EK>, EK: KvEngine> Cluster<T, EK> {
         for id in keys {
             self.stop_node(id);
         }
+        // Injected disk statuses are process-wide, don't leak them to other
+        // test clusters.
+        for id in self.tablet_registries.keys() {
+            disk::clear_disk_status_of_store(*id);
+        }
         self.leaders.clear();
         for store_meta in self.store_metas.values() {
             // Limits the loop count of checking.
@@ -54,6 +54,7 @@ use tempfile::TempDir;
 use test_pd_client::TestPdClient;
 use tikv::server::Result as ServerResult;
 use tikv_util::{
+    sys::disk::{self, DiskUsage},
     thread_group::GroupProperties,
     time::{Instant, ThreadReadId},
     worker::LazyWorker,
@@ -808,6 +809,11 @@ impl<T: Simulator> Cluster<T> {
         for id in keys {
             self.stop_node(id);
         }
+        // Injected disk statuses are process-wide, don't leak them to other
+        // test clusters.
+        for id in self.engines.keys() {
+            disk::clear_disk_status_of_store(*id);
+        }
         self.leaders.clear();
         self.store_metas.clear();
         for sst_worker in self.sst_workers.drain(..) {
@@ -1116,6 +1122,25 @@ impl<T: Simulator> Cluster<T> {
         }
     }
 
+    /// Simulates a disk failure on the given store, e.g. `AlreadyFull` makes
+    /// raftstore reject every proposal that doesn't carry an allowed
+    /// `DiskFullOpt` as if the disk has no space left.
+    pub fn set_disk_usage(&self, store_id: u64, usage: DiskUsage) {
+        disk::set_disk_status_of_store(store_id, usage);
+    }
+
+    /// Recovers the simulated disk failure on the given store.
+    pub fn clear_disk_usage(&self, store_id: u64) {
+        disk::clear_disk_status_of_store(store_id);
+    }
+
+    /// Advances the monotonic clocks of every node by `duration`, so leases
+    /// and timeouts based on them expire without actually sleeping. Note that
+    /// the clocks are process-wide and can't be turned back.
+    pub fn advance_clock(&self, duration: Duration) {
+        tikv_util::time::advance_monotonic_clock(duration);
+    }
+
     pub fn must_get_buckets(&mut self, region_id: u64) -> BucketStat {
         let timer = Instant::now();
         let timeout = Duration::from_secs(5);
@@ -1472,6 +1497,15 @@ impl<T: Simulator> Cluster<T> {
         .unwrap();
     }
 
+    // Sends `CasualMessage::ForceCompactRaftLogs` to the leader of the region,
+    // which compacts the raft log up to the replicated index regardless of the
+    // configured GC limits.
+    pub fn force_compact_raft_logs(&mut self, region_id: u64) {
+        let leader = self.leader_of_region(region_id).unwrap();
+        let router = self.sim.rl().get_router(leader.get_store_id()).unwrap();
+        CasualRouter::send(&router, region_id, CasualMessage::ForceCompactRaftLogs).unwrap();
+    }
+
     pub fn enter_force_leader(&mut self, region_id: u64, store_id: u64, failed_stores: Vec<u64>) {
         let mut plan = pdpb::RecoveryPlan::default();
         let mut force_leader = pdpb::ForceLeader::default();
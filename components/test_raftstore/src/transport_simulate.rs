@@ -5,14 +5,18 @@ use std::{
     mem,
     sync::{atomic::*, mpsc::Sender, Arc, Mutex, RwLock},
     thread, time,
-    time::Duration,
+    time::{Duration, Instant},
     usize,
 };
 
 use collections::{HashMap, HashSet};
 use crossbeam::channel::TrySendError;
 use engine_rocks::{RocksEngine, RocksSnapshot};
-use kvproto::{raft_cmdpb::RaftCmdRequest, raft_serverpb::RaftMessage};
+use kvproto::{
+    raft_cmdpb::RaftCmdRequest,
+    raft_serverpb::{RaftMessage, RaftSnapshotData},
+};
+use protobuf::Message;
 use raft::eraftpb::MessageType;
 use raftstore::{
     router::{LocalReadRouter, RaftStoreRouter},
@@ -341,6 +345,149 @@ impl FilterFactory for IsolationFilterFactory {
     }
 }
 
+// A directed link rule between two stores.
+#[derive(Clone)]
+struct LinkRule {
+    from: u64,
+    to: u64,
+    // `None` drops matched messages, otherwise they are delayed.
+    delay: Option<Duration>,
+    heal_after: Option<Duration>,
+}
+
+/// Declaratively describes partitions and slow links between stores.
+///
+/// Rules are directed, so asymmetric partitions can be expressed by cutting
+/// only one direction of a link. A rule may heal itself after a deadline,
+/// which makes jepsen-like scenarios reproducible without removing filters by
+/// hand:
+///
+/// ```ignore
+/// // Store 1 is cut off from store 2 and 3 for two seconds, and messages
+/// // from store 3 to store 1 are delayed by 100ms.
+/// cluster.add_send_filter(
+///     PartitionPlan::new()
+///         .cut(vec![1], vec![2, 3])
+///         .heal_after(Duration::from_secs(2))
+///         .delay_link(3, 1, Duration::from_millis(100)),
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct PartitionPlan {
+    rules: Vec<LinkRule>,
+    // The number of rules added by the last builder call, `heal_after`
+    // applies to them.
+    last_added: usize,
+}
+
+impl PartitionPlan {
+    pub fn new() -> PartitionPlan {
+        Default::default()
+    }
+
+    fn add_rules(mut self, rules: Vec<LinkRule>) -> PartitionPlan {
+        self.last_added = rules.len();
+        self.rules.extend(rules);
+        self
+    }
+
+    /// Drops all messages sent from `from` to `to`. The other direction is
+    /// not affected.
+    pub fn drop_link(self, from: u64, to: u64) -> PartitionPlan {
+        self.add_rules(vec![LinkRule {
+            from,
+            to,
+            delay: None,
+            heal_after: None,
+        }])
+    }
+
+    /// Cuts all links between the two groups of stores in both directions.
+    pub fn cut(self, s1: Vec<u64>, s2: Vec<u64>) -> PartitionPlan {
+        let mut rules = vec![];
+        for from in &s1 {
+            for to in &s2 {
+                for (from, to) in [(*from, *to), (*to, *from)] {
+                    rules.push(LinkRule {
+                        from,
+                        to,
+                        delay: None,
+                        heal_after: None,
+                    });
+                }
+            }
+        }
+        self.add_rules(rules)
+    }
+
+    /// Delays all messages sent from `from` to `to` by `delay`.
+    pub fn delay_link(self, from: u64, to: u64, delay: Duration) -> PartitionPlan {
+        self.add_rules(vec![LinkRule {
+            from,
+            to,
+            delay: Some(delay),
+            heal_after: None,
+        }])
+    }
+
+    /// Heals the links created by the last builder call after `d`. The
+    /// deadline is counted from the time the plan is applied to a cluster.
+    pub fn heal_after(mut self, d: Duration) -> PartitionPlan {
+        let len = self.rules.len();
+        for rule in &mut self.rules[len - self.last_added..] {
+            rule.heal_after = Some(d);
+        }
+        self
+    }
+}
+
+impl FilterFactory for PartitionPlan {
+    fn generate(&self, node_id: u64) -> Vec<Box<dyn Filter>> {
+        let now = Instant::now();
+        self.rules
+            .iter()
+            .filter(|r| r.from == node_id)
+            .map(|r| {
+                Box::new(LinkFilter {
+                    to: r.to,
+                    delay: r.delay,
+                    deadline: r.heal_after.map(|d| now + d),
+                }) as Box<dyn Filter>
+            })
+            .collect()
+    }
+}
+
+// Drops or delays messages over one directed link until the deadline.
+struct LinkFilter {
+    to: u64,
+    delay: Option<Duration>,
+    deadline: Option<Instant>,
+}
+
+impl Filter for LinkFilter {
+    fn before(&self, msgs: &mut Vec<RaftMessage>) -> Result<()> {
+        if self.deadline.map_or(false, |d| Instant::now() >= d) {
+            return Ok(());
+        }
+        match self.delay {
+            Some(d) => {
+                if msgs
+                    .iter()
+                    .any(|m| m.get_to_peer().get_store_id() == self.to)
+                {
+                    thread::sleep(d);
+                }
+                Ok(())
+            }
+            None => {
+                msgs.retain(|m| m.get_to_peer().get_store_id() != self.to);
+                check_messages(msgs)
+            }
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum Direction {
     Recv,
@@ -622,6 +769,85 @@ impl Filter for DropSnapshotFilter {
     }
 }
 
+/// How [`CorruptSnapshotFilter`] tampers with a snapshot message.
+#[derive(Clone, Copy)]
+pub enum SnapshotTamper {
+    /// Flips the cf file checksums, the receiver fails verification when
+    /// saving the snapshot.
+    Checksum,
+    /// Declares more bytes than are actually sent, as if the stream lost its
+    /// final chunk.
+    Truncate,
+}
+
+/// Corrupts the meta of the first snapshot message it sees, so that the
+/// receiving store fails the integrity check and the snapshot has to be sent
+/// again.
+pub struct CorruptSnapshotFilter {
+    tamper: SnapshotTamper,
+    // Set once a snapshot has been corrupted, later snapshots pass through
+    // so the cluster can recover.
+    pub applied: Arc<AtomicBool>,
+}
+
+impl CorruptSnapshotFilter {
+    pub fn new(tamper: SnapshotTamper, applied: Arc<AtomicBool>) -> CorruptSnapshotFilter {
+        CorruptSnapshotFilter { tamper, applied }
+    }
+}
+
+impl Filter for CorruptSnapshotFilter {
+    fn before(&self, msgs: &mut Vec<RaftMessage>) -> Result<()> {
+        for msg in msgs.iter_mut() {
+            if msg.get_message().get_msg_type() != MessageType::MsgSnapshot
+                || self.applied.swap(true, Ordering::SeqCst)
+            {
+                continue;
+            }
+            let snap = msg.mut_message().mut_snapshot();
+            let mut snap_data = RaftSnapshotData::default();
+            snap_data.merge_from_bytes(snap.get_data()).unwrap();
+            for cf in snap_data.mut_meta().mut_cf_files().iter_mut() {
+                match self.tamper {
+                    SnapshotTamper::Checksum => cf.set_checksum(!cf.get_checksum()),
+                    SnapshotTamper::Truncate => cf.set_size(cf.get_size() + 1024),
+                }
+            }
+            snap.set_data(snap_data.write_to_bytes().unwrap().into());
+        }
+        Ok(())
+    }
+}
+
+/// Throttles snapshot messages as if the snapshot stream were limited to
+/// `bytes_per_sec`, other messages are not affected.
+pub struct ThrottleSnapshotFilter {
+    bytes_per_sec: u64,
+}
+
+impl ThrottleSnapshotFilter {
+    pub fn new(bytes_per_sec: u64) -> ThrottleSnapshotFilter {
+        ThrottleSnapshotFilter { bytes_per_sec }
+    }
+}
+
+impl Filter for ThrottleSnapshotFilter {
+    fn before(&self, msgs: &mut Vec<RaftMessage>) -> Result<()> {
+        for msg in msgs.iter() {
+            if msg.get_message().get_msg_type() != MessageType::MsgSnapshot {
+                continue;
+            }
+            let mut snap_data = RaftSnapshotData::default();
+            snap_data
+                .merge_from_bytes(msg.get_message().get_snapshot().get_data())
+                .unwrap();
+            let secs = snap_data.get_file_size() as f64 / self.bytes_per_sec as f64;
+            thread::sleep(Duration::from_secs_f64(secs));
+        }
+        Ok(())
+    }
+}
+
 /// Capture the first snapshot message.
 pub struct RecvSnapshotFilter {
     pub notifier: Mutex<Option<Sender<RaftMessage>>>,
@@ -28,6 +28,7 @@ pub fn new_file_security_config(dir: &Path) -> EncryptionConfig {
     EncryptionConfig {
         data_encryption_method: EncryptionMethod::Aes256Ctr,
         data_key_rotation_period: ReadableDuration::days(7),
+        data_key_rotation_file_threshold: 0,
         enable_file_dictionary_log: true,
         file_dictionary_rewrite_threshold: 100000,
         master_key: master_key_cfg.clone(),
@@ -50,6 +51,7 @@ pub fn new_test_key_manager(
         DataKeyManagerArgs {
             method: method.unwrap_or(EncryptionMethod::Aes256Ctr),
             rotation_period: Duration::from_secs(60),
+            rotation_file_threshold: 0,
             enable_file_dictionary_log: true,
             file_dictionary_rewrite_threshold: 2,
             dict_path: tmp_dir.path().to_str().unwrap().to_string(),
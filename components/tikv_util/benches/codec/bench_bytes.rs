@@ -0,0 +1,55 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use test::Bencher;
+use tikv_util::codec::bytes::{
+    decode_bytes, decode_bytes_in_place, decode_bytes_to_buf, encode_bytes, encode_bytes_desc,
+};
+
+// A typical multi-group key, e.g. a TiDB index key.
+const KEY_LEN: usize = 64;
+
+fn encoded_key() -> Vec<u8> {
+    encode_bytes(&vec![b'x'; KEY_LEN])
+}
+
+#[bench]
+fn bench_encode_bytes(b: &mut Bencher) {
+    let key = vec![b'x'; KEY_LEN];
+    b.iter(|| test::black_box(encode_bytes(&key)));
+}
+
+#[bench]
+fn bench_encode_bytes_desc(b: &mut Bencher) {
+    let key = vec![b'x'; KEY_LEN];
+    b.iter(|| test::black_box(encode_bytes_desc(&key)));
+}
+
+#[bench]
+fn bench_decode_bytes(b: &mut Bencher) {
+    let encoded = encoded_key();
+    b.iter(|| {
+        let mut data = encoded.as_slice();
+        test::black_box(decode_bytes(&mut data, false).unwrap());
+    });
+}
+
+#[bench]
+fn bench_decode_bytes_in_place(b: &mut Bencher) {
+    let encoded = encoded_key();
+    b.iter(|| {
+        let mut data = encoded.clone();
+        decode_bytes_in_place(&mut data, false).unwrap();
+        test::black_box(&data);
+    });
+}
+
+#[bench]
+fn bench_decode_bytes_to_buf(b: &mut Bencher) {
+    let encoded = encoded_key();
+    let mut buf = Vec::with_capacity(KEY_LEN * 2);
+    b.iter(|| {
+        buf.clear();
+        let mut data = encoded.as_slice();
+        test::black_box(decode_bytes_to_buf(&mut data, false, &mut buf).unwrap());
+    });
+}
@@ -0,0 +1,7 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+#![feature(test)]
+
+extern crate test;
+
+mod bench_bytes;
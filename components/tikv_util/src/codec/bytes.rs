@@ -89,10 +89,56 @@ pub fn encode_bytes_desc(bs: &[u8]) -> Vec<u8> {
 fn encode_order_bytes(bs: &[u8], desc: bool) -> Vec<u8> {
     let cap = max_encoded_bytes_size(bs.len());
     let mut encoded = Vec::with_capacity(cap);
-    encoded.encode_bytes(bs, desc).unwrap();
+    encoded.encode_bytes(bs, false).unwrap();
+    if desc {
+        // The descending encoding is exactly the bitwise complement of the
+        // ascending one, paddings and markers included, so encode ascending
+        // and invert the result in one vectorized pass.
+        invert_bytes(&mut encoded);
+    }
     encoded
 }
 
+/// Inverts every byte of `bytes`, using AVX2 when the CPU supports it.
+fn invert_bytes(bytes: &mut [u8]) {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        // The detection result is cached by std, so probing here is cheap.
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support has just been checked.
+            unsafe { invert_bytes_avx2(bytes) };
+            return;
+        }
+    }
+    invert_bytes_scalar(bytes);
+}
+
+fn invert_bytes_scalar(bytes: &mut [u8]) {
+    for b in bytes {
+        *b = !*b;
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2")]
+unsafe fn invert_bytes_avx2(bytes: &mut [u8]) {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    let ones = _mm256_set1_epi8(-1);
+    let mut chunks = bytes.chunks_exact_mut(32);
+    for chunk in &mut chunks {
+        let v = _mm256_loadu_si256(chunk.as_ptr() as *const __m256i);
+        _mm256_storeu_si256(
+            chunk.as_mut_ptr() as *mut __m256i,
+            _mm256_xor_si256(v, ones),
+        );
+    }
+    invert_bytes_scalar(chunks.into_remainder());
+}
+
 /// Gets the first encoded bytes' length in compactly encoded data.
 ///
 /// Compact-encoding includes a VarInt encoded length prefix (1 ~ 9 bytes) and N
@@ -213,9 +259,7 @@ pub fn decode_bytes(data: &mut BytesSlice<'_>, desc: bool) -> Result<Vec<u8>> {
         }
 
         if desc {
-            for k in &mut key {
-                *k = !*k;
-            }
+            invert_bytes(&mut key);
         }
         // data will point to following unencoded bytes, maybe timestamp
         *data = &data[offset..];
@@ -223,6 +267,63 @@ pub fn decode_bytes(data: &mut BytesSlice<'_>, desc: bool) -> Result<Vec<u8>> {
     }
 }
 
+/// Decodes bytes which are encoded by `encode_bytes` before, appending the
+/// decoded key to `buf` instead of allocating a `Vec`, and returns the
+/// decoded part as a slice of `buf`. Scans decoding many keys can clear and
+/// reuse one buffer to avoid the per-key allocation of `decode_bytes`.
+pub fn decode_bytes_to_buf<'a>(
+    data: &mut BytesSlice<'_>,
+    desc: bool,
+    buf: &'a mut Vec<u8>,
+) -> Result<&'a [u8]> {
+    let start = buf.len();
+    let mut offset = 0;
+    let chunk_len = ENC_GROUP_SIZE + 1;
+    loop {
+        // everytime make ENC_GROUP_SIZE + 1 elements as a decode unit
+        let next_offset = offset + chunk_len;
+        let chunk = if next_offset <= data.len() {
+            &data[offset..next_offset]
+        } else {
+            buf.truncate(start);
+            return Err(Error::unexpected_eof());
+        };
+        offset = next_offset;
+        // the last byte in decode unit is for marker which indicates pad size
+        let (&marker, bytes) = chunk.split_last().unwrap();
+        let pad_size = if desc {
+            marker as usize
+        } else {
+            (ENC_MARKER - marker) as usize
+        };
+        // no padding, just push 8 bytes
+        if pad_size == 0 {
+            buf.extend_from_slice(bytes);
+            continue;
+        }
+        if pad_size > ENC_GROUP_SIZE {
+            buf.truncate(start);
+            return Err(Error::KeyPadding);
+        }
+        // if has padding, split the padding pattern and push rest bytes
+        let (bytes, padding) = bytes.split_at(ENC_GROUP_SIZE - pad_size);
+        buf.extend_from_slice(bytes);
+        let pad_byte = if desc { !0 } else { 0 };
+        // check the padding pattern whether validate or not
+        if padding.iter().any(|x| *x != pad_byte) {
+            buf.truncate(start);
+            return Err(Error::KeyPadding);
+        }
+
+        if desc {
+            invert_bytes(&mut buf[start..]);
+        }
+        // data will point to following unencoded bytes, maybe timestamp
+        *data = &data[offset..];
+        return Ok(&buf[start..]);
+    }
+}
+
 /// Decodes bytes which are encoded by `encode_bytes` before just in place
 /// without malloc. Please use this instead of `decode_bytes` if possible.
 pub fn decode_bytes_in_place(data: &mut Vec<u8>, desc: bool) -> Result<()> {
@@ -274,9 +375,7 @@ pub fn decode_bytes_in_place(data: &mut Vec<u8>, desc: bool) -> Result<()> {
                 data.set_len(write_offset - pad_size);
             }
             if desc {
-                for k in data {
-                    *k = !*k;
-                }
+                invert_bytes(data);
             }
             return Ok(());
         }
@@ -450,6 +549,58 @@ mod tests {
         for mut x in invalid_bytes {
             decode_bytes(&mut x.as_slice(), false).unwrap_err();
             decode_bytes_in_place(&mut x, false).unwrap_err();
+            let mut buf = b"prefix".to_vec();
+            decode_bytes_to_buf(&mut x.as_slice(), false, &mut buf).unwrap_err();
+            // Failed decodes must not leave partial bytes in the buffer.
+            assert_eq!(buf, b"prefix");
+        }
+    }
+
+    #[test]
+    fn test_decode_bytes_to_buf() {
+        let mut buf = Vec::new();
+        for len in 0..=24usize {
+            let key: Vec<u8> = (0..len as u8).collect();
+            for desc in [false, true] {
+                let encoded = encode_order_bytes(&key, desc);
+                let mut data = encoded.as_slice();
+                buf.clear();
+                assert_eq!(
+                    decode_bytes_to_buf(&mut data, desc, &mut buf).unwrap(),
+                    key.as_slice()
+                );
+                assert!(data.is_empty());
+            }
+        }
+
+        // Decoded keys are appended, earlier contents are left intact.
+        let mut buf = b"prefix".to_vec();
+        let encoded = encode_bytes(b"key");
+        let mut data = encoded.as_slice();
+        assert_eq!(
+            decode_bytes_to_buf(&mut data, false, &mut buf).unwrap(),
+            b"key"
+        );
+        assert_eq!(buf, b"prefixkey");
+    }
+
+    #[test]
+    fn test_invert_bytes() {
+        // Lengths around the SIMD width cover both the vectorized loop and
+        // the scalar remainder.
+        for len in [0, 1, 31, 32, 33, 63, 64, 100] {
+            let original: Vec<u8> = (0..len as u8).collect();
+            let mut inverted = original.clone();
+            invert_bytes(&mut inverted);
+            assert!(
+                original
+                    .iter()
+                    .zip(&inverted)
+                    .all(|(&a, &b)| a == !b),
+                "{:?} {:?}",
+                original,
+                inverted
+            );
         }
     }
 
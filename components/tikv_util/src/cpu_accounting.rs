@@ -0,0 +1,116 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Per-task CPU accounting for futures running on yatp or tokio pools.
+//!
+//! [`CpuAccountedFuture`] measures the thread CPU time spent in every poll of
+//! the wrapped future and accumulates it into a counter tagged by task type.
+//! A pool only needs to wrap its tasks to report CPU breakdowns, instead of
+//! instrumenting the read pool, apply pool and backup workers one by one.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use lazy_static::lazy_static;
+use pin_project::pin_project;
+use prometheus::*;
+
+use crate::sys::cpu_time::thread_cpu_time;
+
+lazy_static! {
+    pub static ref TASK_CPU_SECONDS: CounterVec = register_counter_vec!(
+        "tikv_task_cpu_seconds_total",
+        "Total CPU time consumed by tasks, tagged by task type.",
+        &["type"]
+    )
+    .unwrap();
+}
+
+/// A cloneable handle reporting the CPU time a task has consumed so far.
+#[derive(Clone, Default)]
+pub struct TaskCpuHandle(Arc<AtomicU64>);
+
+impl TaskCpuHandle {
+    /// Returns the CPU time the task has accumulated over all its polls.
+    pub fn cpu_time(&self) -> Duration {
+        Duration::from_nanos(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// A future that accounts the thread CPU time spent polling it.
+#[pin_project]
+pub struct CpuAccountedFuture<F> {
+    #[pin]
+    future: F,
+    counter: Counter,
+    handle: TaskCpuHandle,
+}
+
+impl<F> CpuAccountedFuture<F> {
+    /// Wraps a future so the CPU time spent polling it is accumulated under
+    /// the given task type.
+    pub fn new(task_type: &str, future: F) -> CpuAccountedFuture<F> {
+        CpuAccountedFuture {
+            future,
+            counter: TASK_CPU_SECONDS.with_label_values(&[task_type]),
+            handle: TaskCpuHandle::default(),
+        }
+    }
+
+    /// Returns a handle to the CPU time of this task, e.g. to feed resource
+    /// metering.
+    pub fn handle(&self) -> TaskCpuHandle {
+        self.handle.clone()
+    }
+}
+
+impl<F: Future> Future for CpuAccountedFuture<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        // If the platform fails to report thread CPU time the task still
+        // runs, only its accounting is lost.
+        let start = thread_cpu_time();
+        let res = this.future.poll(cx);
+        if let (Ok(start), Ok(end)) = (start, thread_cpu_time()) {
+            let spent = end.saturating_sub(start);
+            this.handle
+                .0
+                .fetch_add(spent.as_nanos() as u64, Ordering::Relaxed);
+            this.counter.inc_by(spent.as_secs_f64());
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[test]
+    fn test_account_cpu_time() {
+        let fut = CpuAccountedFuture::new("test", async {
+            let mut acc = 0u64;
+            for i in 0..10_000_000u64 {
+                acc = acc.wrapping_add(i);
+            }
+            // Keep the loop from being optimized out.
+            assert!(acc > 0);
+        });
+        let handle = fut.handle();
+        assert_eq!(handle.cpu_time(), Duration::from_nanos(0));
+        block_on(fut);
+        assert!(handle.cpu_time() > Duration::from_nanos(0));
+        assert!(TASK_CPU_SECONDS.with_label_values(&["test"]).get() > 0.0);
+    }
+}
@@ -41,6 +41,7 @@ pub mod log;
 pub mod buffer_vec;
 pub mod codec;
 pub mod config;
+pub mod cpu_accounting;
 pub mod future;
 #[macro_use]
 pub mod macros;
@@ -55,8 +56,11 @@ pub mod metrics;
 pub mod mpsc;
 pub mod quota_limiter;
 pub mod resource_control;
+pub mod slow_trace;
+pub mod speed_limit;
 pub mod store;
 pub mod stream;
+pub mod sync_scheduler;
 pub mod sys;
 pub mod thread_group;
 pub mod time;
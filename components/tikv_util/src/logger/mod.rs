@@ -905,6 +905,41 @@ mod tests {
         check_log(expected);
     }
 
+    #[test]
+    fn test_slow_log_filter() {
+        let decorator = PlainSyncDecorator::new(TestWriter);
+        let drain = TikvFormat::new(decorator, true).fuse();
+        let drain = SlowLogFilter {
+            threshold: 100,
+            inner: drain,
+        }
+        .fuse();
+        let logger = slog::Logger::root_typed(drain, slog_o!()).into_erased();
+
+        let check_is_logged = |expected: bool| {
+            BUFFER.with(|buffer| {
+                let mut buffer = buffer.borrow_mut();
+                let output = from_utf8(&buffer).unwrap();
+                assert_eq!(!output.is_empty(), expected, "{:?}", output);
+                buffer.clear();
+            });
+        };
+
+        // Fast operations under the slow_log tag are filtered out.
+        slog_warn!(logger, #"slow_log", "fast"; "takes" => LogCost(50));
+        check_is_logged(false);
+
+        slog_warn!(logger, #"slow_log", "slow"; "takes" => LogCost(150));
+        check_is_logged(true);
+
+        // Records without a cost or with another tag pass through.
+        slog_warn!(logger, #"slow_log", "no cost");
+        check_is_logged(true);
+
+        slog_warn!(logger, "normal"; "takes" => LogCost(50));
+        check_is_logged(true);
+    }
+
     /// Removes the wrapping signs, peels `"[hello]"` to `"hello"`, or peels
     /// `"(hello)"` to `"hello"`,
     fn peel(output: &str) -> &str {
@@ -418,6 +418,17 @@ impl ThreadInfoStatistics {
     pub fn get_write_io_rates(&self) -> HashMap<String, u64> {
         collect_metrics_by_name(&self.tid_names, &self.metrics_rate.write_ios)
     }
+
+    /// Returns the `n` hottest thread groups by CPU usage over the last
+    /// recorded interval, sorted in descending order. Threads are grouped
+    /// by their sanitized name prefix (e.g. all `grpc-server-*` threads are
+    /// accounted together).
+    pub fn get_top_cpu_usages(&self, n: usize) -> Vec<(String, u64)> {
+        let mut usages: Vec<_> = self.get_cpu_usages().into_iter().collect();
+        usages.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        usages.truncate(n);
+        usages
+    }
 }
 
 impl Default for ThreadInfoStatistics {
@@ -673,6 +684,21 @@ mod tests {
         panic!();
     }
 
+    #[test]
+    fn test_top_cpu_usages() {
+        let mut thread_info = ThreadInfoStatistics::new();
+        thread_info.record();
+
+        let total = thread_info.get_cpu_usages().len();
+        assert_eq!(thread_info.get_top_cpu_usages(usize::MAX).len(), total);
+
+        let top = thread_info.get_top_cpu_usages(3);
+        assert!(top.len() <= 3);
+        for pair in top.windows(2) {
+            assert!(pair[0].1 >= pair[1].1);
+        }
+    }
+
     fn get_thread_name(stat: &str) -> Result<(&str, usize)> {
         let start = stat.find('(');
         let end = stat.rfind(')');
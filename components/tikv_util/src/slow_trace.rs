@@ -0,0 +1,198 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! A lightweight tracing facility for slow operations.
+//!
+//! A request handler starts a trace with [`start_trace`], the code it calls
+//! into annotates interesting stages with [`span`], and when the request
+//! finishes [`TraceGuard::finish`] returns the recorded breakdown if the
+//! request took longer than the caller's slow threshold. Collection is
+//! thread local and a span outside any active trace is a cheap no-op, so
+//! the write path can be annotated unconditionally.
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use tikv_util::slow_trace::{span, start_trace};
+//!
+//! let trace = start_trace();
+//! {
+//!     let _s = span("raftstore.propose");
+//!     // ... propose ...
+//! }
+//! if let Some(spans) = trace.finish(Duration::from_millis(100)) {
+//!     // Attach the breakdown to the response or slow log.
+//!     let _ = spans;
+//! }
+//! ```
+
+use std::{cell::RefCell, fmt, time::Duration};
+
+use crate::time::Instant;
+
+thread_local! {
+    static CURRENT_TRACE: RefCell<Option<TraceContext>> = RefCell::new(None);
+}
+
+/// One recorded stage of a traced operation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanRecord {
+    pub name: &'static str,
+    /// Offset of the span start from the trace start.
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+impl fmt::Display for SpanRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:?} (+{:?})", self.name, self.duration, self.start)
+    }
+}
+
+struct TraceContext {
+    start: Instant,
+    spans: Vec<SpanRecord>,
+}
+
+/// Starts collecting spans on the current thread until the returned guard is
+/// finished or dropped. Starting a trace while another one is active on the
+/// thread returns an inactive guard, the outer trace keeps collecting.
+pub fn start_trace() -> TraceGuard {
+    CURRENT_TRACE.with(|c| {
+        let mut current = c.borrow_mut();
+        if current.is_some() {
+            return TraceGuard { active: false };
+        }
+        *current = Some(TraceContext {
+            start: Instant::now_coarse(),
+            spans: Vec::new(),
+        });
+        TraceGuard { active: true }
+    })
+}
+
+/// Records a span named `name` lasting until the returned guard is dropped.
+/// Does nothing when no trace is active on the current thread.
+pub fn span(name: &'static str) -> SpanGuard {
+    let active = CURRENT_TRACE.with(|c| c.borrow().is_some());
+    SpanGuard {
+        name,
+        start: if active {
+            Some(Instant::now_coarse())
+        } else {
+            None
+        },
+    }
+}
+
+#[must_use = "the span lasts until the guard is dropped"]
+pub struct SpanGuard {
+    name: &'static str,
+    start: Option<Instant>,
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        let start = match self.start {
+            Some(start) => start,
+            None => return,
+        };
+        let now = Instant::now_coarse();
+        CURRENT_TRACE.with(|c| {
+            if let Some(ctx) = c.borrow_mut().as_mut() {
+                ctx.spans.push(SpanRecord {
+                    name: self.name,
+                    start: start.saturating_duration_since(ctx.start),
+                    duration: now.saturating_duration_since(start),
+                });
+            }
+        });
+    }
+}
+
+#[must_use = "the trace lasts until the guard is finished or dropped"]
+pub struct TraceGuard {
+    active: bool,
+}
+
+impl TraceGuard {
+    /// Stops collecting and returns the recorded spans if the traced
+    /// operation exceeded `threshold`, otherwise the records are dropped.
+    pub fn finish(mut self, threshold: Duration) -> Option<Vec<SpanRecord>> {
+        if !self.active {
+            return None;
+        }
+        self.active = false;
+        CURRENT_TRACE.with(|c| {
+            let ctx = c.borrow_mut().take()?;
+            if ctx.start.saturating_elapsed() >= threshold {
+                Some(ctx.spans)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl Drop for TraceGuard {
+    fn drop(&mut self) {
+        if self.active {
+            CURRENT_TRACE.with(|c| c.borrow_mut().take());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_spans() {
+        let trace = start_trace();
+        {
+            let _s = span("stage-1");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        {
+            let _s = span("stage-2");
+        }
+        let spans = trace.finish(Duration::from_millis(1)).unwrap();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].name, "stage-1");
+        assert_eq!(spans[1].name, "stage-2");
+        assert!(spans[1].start >= spans[0].duration);
+    }
+
+    #[test]
+    fn test_fast_operations_are_dropped() {
+        let trace = start_trace();
+        {
+            let _s = span("stage");
+        }
+        assert_eq!(trace.finish(Duration::from_secs(3600)), None);
+    }
+
+    #[test]
+    fn test_span_without_trace() {
+        // Spans outside a trace are no-ops and must not leak into the next
+        // trace on the thread.
+        {
+            let _s = span("orphan");
+        }
+        let trace = start_trace();
+        let spans = trace.finish(Duration::ZERO).unwrap();
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn test_nested_trace_is_inactive() {
+        let outer = start_trace();
+        let inner = start_trace();
+        assert_eq!(inner.finish(Duration::ZERO), None);
+        // The outer trace is still collecting.
+        {
+            let _s = span("stage");
+        }
+        let spans = outer.finish(Duration::ZERO).unwrap();
+        assert_eq!(spans.len(), 1);
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Utilities to divide a single speed limit between classes of work.
+
+use std::sync::Mutex;
+
+use collections::HashMap;
+
+use crate::time::Limiter;
+
+struct Class {
+    weight: f64,
+    limiter: Limiter,
+}
+
+struct Inner {
+    speed_limit: f64,
+    classes: HashMap<String, Class>,
+}
+
+impl Inner {
+    // Recomputes the budget of every class from its weight.
+    fn refresh(&self) {
+        let total_weight: f64 = self.classes.values().map(|c| c.weight).sum();
+        for class in self.classes.values() {
+            let share = if self.speed_limit.is_finite() && total_weight > 0.0 {
+                self.speed_limit * class.weight / total_weight
+            } else {
+                f64::INFINITY
+            };
+            class.limiter.set_speed_limit(share);
+        }
+    }
+}
+
+/// Divides one global speed limit between several classes of work by weight.
+///
+/// Each registered class owns a plain [`Limiter`] whose budget is
+/// `speed_limit * weight / total_weight`, so a single bandwidth knob can be
+/// fairly shared between e.g. foreground writes, backup and snapshots.
+/// Weights and the global budget can both be adjusted at runtime and all
+/// class limiters are refreshed accordingly.
+pub struct HierarchicalLimiter {
+    inner: Mutex<Inner>,
+}
+
+impl HierarchicalLimiter {
+    /// Creates a limiter group with the given global speed limit in
+    /// bytes per second. `f64::INFINITY` disables the limit.
+    pub fn new(speed_limit: f64) -> HierarchicalLimiter {
+        HierarchicalLimiter {
+            inner: Mutex::new(Inner {
+                speed_limit,
+                classes: HashMap::default(),
+            }),
+        }
+    }
+
+    /// Registers a class with the given weight and returns the limiter
+    /// enforcing its share. The shares of all registered classes are
+    /// recomputed.
+    ///
+    /// Registering a name twice replaces the old class, but the limiter
+    /// handed out before keeps its last budget and is not refreshed any
+    /// more.
+    pub fn register(&self, name: &str, weight: f64) -> Limiter {
+        let mut inner = self.inner.lock().unwrap();
+        let limiter = Limiter::new(f64::INFINITY);
+        inner.classes.insert(
+            name.to_owned(),
+            Class {
+                weight,
+                limiter: limiter.clone(),
+            },
+        );
+        inner.refresh();
+        limiter
+    }
+
+    /// Removes a class and redistributes its share to the remaining ones.
+    pub fn deregister(&self, name: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.classes.remove(name).is_some() {
+            inner.refresh();
+        }
+    }
+
+    /// Adjusts the weight of a registered class. Returns false if the class
+    /// is unknown.
+    pub fn set_weight(&self, name: &str, weight: f64) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.classes.get_mut(name) {
+            Some(class) => class.weight = weight,
+            None => return false,
+        }
+        inner.refresh();
+        true
+    }
+
+    /// Returns the global speed limit in bytes per second.
+    pub fn speed_limit(&self) -> f64 {
+        self.inner.lock().unwrap().speed_limit
+    }
+
+    /// Resizes the global budget, the share of every class is scaled
+    /// accordingly.
+    pub fn set_speed_limit(&self, speed_limit: f64) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.speed_limit = speed_limit;
+        inner.refresh();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_shares() {
+        let group = HierarchicalLimiter::new(8000.0);
+        let foreground = group.register("foreground", 3.0);
+        let backup = group.register("backup", 1.0);
+        assert_eq!(foreground.speed_limit(), 6000.0);
+        assert_eq!(backup.speed_limit(), 2000.0);
+
+        // Re-weighting refreshes every class.
+        assert!(group.set_weight("backup", 3.0));
+        assert!(!group.set_weight("snapshot", 1.0));
+        assert_eq!(foreground.speed_limit(), 4000.0);
+        assert_eq!(backup.speed_limit(), 4000.0);
+
+        // So does resizing the global budget.
+        group.set_speed_limit(4000.0);
+        assert_eq!(group.speed_limit(), 4000.0);
+        assert_eq!(foreground.speed_limit(), 2000.0);
+        assert_eq!(backup.speed_limit(), 2000.0);
+
+        // Removing a class redistributes its share.
+        group.deregister("backup");
+        assert_eq!(foreground.speed_limit(), 4000.0);
+    }
+
+    #[test]
+    fn test_unlimited() {
+        let group = HierarchicalLimiter::new(f64::INFINITY);
+        let snapshot = group.register("snapshot", 1.0);
+        assert!(snapshot.speed_limit().is_infinite());
+
+        group.set_speed_limit(1000.0);
+        assert_eq!(snapshot.speed_limit(), 1000.0);
+
+        group.set_speed_limit(f64::INFINITY);
+        assert!(snapshot.speed_limit().is_infinite());
+    }
+}
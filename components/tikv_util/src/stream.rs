@@ -211,6 +211,20 @@ impl RetryError for HttpDispatchError {
     }
 }
 
+impl RetryError for io::Error {
+    fn is_retryable(&self) -> bool {
+        matches!(
+            self.kind(),
+            io::ErrorKind::Interrupted
+                | io::ErrorKind::TimedOut
+                | io::ErrorKind::WouldBlock
+                | io::ErrorKind::BrokenPipe
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{cell::RefCell, pin::Pin};
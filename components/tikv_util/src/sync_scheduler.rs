@@ -0,0 +1,223 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! A background scheduler that coalesces and rate-limits file syncs.
+//!
+//! Heavy writers like snapshot generation can issue an fsync per file or even
+//! per batch, which starves foreground writes on slow disks. The scheduler
+//! funnels sync requests through one background thread, merges requests for
+//! the same file, paces the resulting fsyncs with a [`Limiter`] and lets the
+//! caller choose with [`SyncPolicy`] how much durability lag to trade for
+//! fewer syncs.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io,
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{Builder, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    sys::thread::StdThreadBuildWrapper,
+    time::{Instant, Limiter},
+};
+
+/// When to actually call `sync_all` for scheduled files.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncPolicy {
+    /// Every request is synced as soon as the background thread picks it up.
+    Immediate,
+    /// Requests are synced once the given number of distinct files is
+    /// pending.
+    Batched(usize),
+    /// Pending requests are synced at the given interval.
+    Periodic(Duration),
+}
+
+/// Invoked with the result of the `sync_all` call covering the request.
+pub type SyncCallback = Box<dyn FnOnce(io::Result<()>) + Send>;
+
+enum Msg {
+    Sync {
+        file: Arc<File>,
+        cb: Option<SyncCallback>,
+    },
+    Stop,
+}
+
+/// Schedules file syncs onto a background thread. Dropping the scheduler
+/// flushes all pending syncs before the thread exits.
+pub struct SyncScheduler {
+    tx: Sender<Msg>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl SyncScheduler {
+    /// Creates a scheduler syncing with the given policy and at most
+    /// `syncs_per_sec` fsyncs per second. `f64::INFINITY` disables pacing.
+    pub fn new(policy: SyncPolicy, syncs_per_sec: f64) -> SyncScheduler {
+        let (tx, rx) = mpsc::channel();
+        let handle = Builder::new()
+            .name(thd_name!("sync-sched"))
+            .spawn_wrapper(move || {
+                let mut worker = Worker {
+                    limiter: Limiter::new(syncs_per_sec),
+                    pending: HashMap::new(),
+                    last_flush: Instant::now_coarse(),
+                };
+                loop {
+                    let timeout = match policy {
+                        SyncPolicy::Periodic(interval) => interval
+                            .checked_sub(worker.last_flush.saturating_elapsed())
+                            .unwrap_or(Duration::ZERO),
+                        _ => Duration::from_secs(u64::MAX / 4),
+                    };
+                    match rx.recv_timeout(timeout) {
+                        Ok(Msg::Sync { file, cb }) => {
+                            worker.add(file, cb);
+                            match policy {
+                                SyncPolicy::Immediate => worker.flush(),
+                                SyncPolicy::Batched(limit) => {
+                                    if worker.pending.len() >= limit {
+                                        worker.flush();
+                                    }
+                                }
+                                SyncPolicy::Periodic(_) => {}
+                            }
+                        }
+                        Err(RecvTimeoutError::Timeout) => worker.flush(),
+                        Ok(Msg::Stop) | Err(RecvTimeoutError::Disconnected) => {
+                            worker.flush();
+                            return;
+                        }
+                    }
+                }
+            })
+            .unwrap();
+        SyncScheduler {
+            tx,
+            handle: Mutex::new(Some(handle)),
+        }
+    }
+
+    /// Schedules `file` to be synced according to the policy. Requests for
+    /// the same file are merged into a single fsync. The callback, if any,
+    /// is invoked on the background thread with the sync result.
+    pub fn schedule(&self, file: Arc<File>, cb: Option<SyncCallback>) {
+        // The receiver only disconnects on drop, which flushes first.
+        let _ = self.tx.send(Msg::Sync { file, cb });
+    }
+}
+
+impl Drop for SyncScheduler {
+    fn drop(&mut self) {
+        let _ = self.tx.send(Msg::Stop);
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+struct Worker {
+    limiter: Limiter,
+    // Pending files keyed by their `Arc` address for coalescing.
+    pending: HashMap<usize, (Arc<File>, Vec<SyncCallback>)>,
+    last_flush: Instant,
+}
+
+impl Worker {
+    fn add(&mut self, file: Arc<File>, cb: Option<SyncCallback>) {
+        let entry = self
+            .pending
+            .entry(Arc::as_ptr(&file) as usize)
+            .or_insert_with(|| (file, Vec::new()));
+        entry.1.extend(cb);
+    }
+
+    fn flush(&mut self) {
+        for (file, cbs) in std::mem::take(&mut self.pending).into_values() {
+            self.limiter.blocking_consume(1);
+            let res = file.sync_all();
+            for cb in cbs {
+                // `io::Error` is not clonable, rebuild it per callback.
+                cb(res
+                    .as_ref()
+                    .map(|_| ())
+                    .map_err(|e| io::Error::new(e.kind(), e.to_string())));
+            }
+        }
+        self.last_flush = Instant::now_coarse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::channel;
+
+    use tempfile::tempfile;
+
+    use super::*;
+
+    fn must_sync(scheduler: &SyncScheduler, file: &Arc<File>) {
+        let (tx, rx) = channel();
+        scheduler.schedule(
+            file.clone(),
+            Some(Box::new(move |res| tx.send(res).unwrap())),
+        );
+        rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_immediate_sync() {
+        let scheduler = SyncScheduler::new(SyncPolicy::Immediate, f64::INFINITY);
+        let file = Arc::new(tempfile().unwrap());
+        must_sync(&scheduler, &file);
+    }
+
+    #[test]
+    fn test_batched_sync_coalesces() {
+        let scheduler = SyncScheduler::new(SyncPolicy::Batched(2), f64::INFINITY);
+        let file1 = Arc::new(tempfile().unwrap());
+        let file2 = Arc::new(tempfile().unwrap());
+        let (tx, rx) = channel();
+        for _ in 0..3 {
+            // Duplicated requests for one file count as one pending sync.
+            let tx = tx.clone();
+            scheduler.schedule(
+                file1.clone(),
+                Some(Box::new(move |res| tx.send(res).unwrap())),
+            );
+        }
+        rx.recv_timeout(Duration::from_millis(100)).unwrap_err();
+        // The second distinct file completes the batch.
+        scheduler.schedule(file2.clone(), None);
+        for _ in 0..3 {
+            rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_periodic_sync() {
+        let scheduler =
+            SyncScheduler::new(SyncPolicy::Periodic(Duration::from_millis(50)), f64::INFINITY);
+        let file = Arc::new(tempfile().unwrap());
+        must_sync(&scheduler, &file);
+    }
+
+    #[test]
+    fn test_flush_on_drop() {
+        let scheduler = SyncScheduler::new(SyncPolicy::Batched(usize::MAX), f64::INFINITY);
+        let file = Arc::new(tempfile().unwrap());
+        let (tx, rx) = channel();
+        scheduler.schedule(
+            file.clone(),
+            Some(Box::new(move |res| tx.send(res).unwrap())),
+        );
+        drop(scheduler);
+        rx.recv_timeout(Duration::from_secs(5)).unwrap().unwrap();
+    }
+}
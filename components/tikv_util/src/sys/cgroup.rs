@@ -70,11 +70,19 @@ impl CGroupSys {
         let lines = read_to_string("/proc/self/cgroup")
             .map_err(|e| format!("fail to read /proc/self/cgroup: {}", e))?;
         let is_v2 = is_cgroup2_unified_mode()?;
-        let (cgroups, mount_points) = if !is_v2 {
+        let (cgroups, mut mount_points) = if !is_v2 {
             (parse_proc_cgroup_v1(&lines), cgroup_mountinfos_v1())
         } else {
             (parse_proc_cgroup_v2(&lines), cgroup_mountinfos_v2())
         };
+        if !is_v2 {
+            // In hybrid mode some controllers are only enabled in the unified
+            // (v2) hierarchy, which /proc/self/cgroup reports as "0::$PATH".
+            // Record its mount point so lookups can fall back to it.
+            for (controller, mount) in cgroup_mountinfos_v2() {
+                mount_points.entry(controller).or_insert(mount);
+            }
+        }
 
         Ok(CGroupSys {
             cgroups,
@@ -85,39 +93,23 @@ impl CGroupSys {
 
     /// -1 means no limit.
     pub fn memory_limit_in_bytes(&self) -> Option<u64> {
-        let component = if self.is_v2 { "" } else { "memory" };
-        if let Some(group) = self.cgroups.get(component) {
-            if let Some((root, mount_point)) = self.mount_points.get(component) {
-                if let Some(path) = build_path(group, root, mount_point) {
-                    let path = if self.is_v2 {
-                        format!("{}/memory.max", path.to_str().unwrap())
-                    } else {
-                        format!("{}/memory.limit_in_bytes", path.to_str().unwrap())
-                    };
-                    return read_to_string(path)
-                        .map(|x| parse_memory_max(x.trim()))
-                        .ok()
-                        .flatten();
-                }
-            } else {
-                warn!("cgroup memory controller found but not mounted.");
-            }
-        }
-        None
+        let (path, v2) = self.controller_path("memory")?;
+        let path = if v2 {
+            format!("{}/memory.max", path.to_str().unwrap())
+        } else {
+            format!("{}/memory.limit_in_bytes", path.to_str().unwrap())
+        };
+        read_to_string(path)
+            .map(|x| parse_memory_max(x.trim()))
+            .ok()
+            .flatten()
     }
 
     pub fn cpuset_cores(&self) -> HashSet<usize> {
-        let component = if self.is_v2 { "" } else { "cpuset" };
-        if let Some(group) = self.cgroups.get(component) {
-            if let Some((root, mount_point)) = self.mount_points.get(component) {
-                if let Some(path) = build_path(group, root, mount_point) {
-                    let path = format!("{}/cpuset.cpus", path.to_str().unwrap());
-                    if let Ok(s) = read_to_string(path) {
-                        return parse_cpu_cores(s.trim());
-                    }
-                }
-            } else {
-                warn!("cgroup cpuset controller found but not mounted.");
+        if let Some((path, _)) = self.controller_path("cpuset") {
+            let path = format!("{}/cpuset.cpus", path.to_str().unwrap());
+            if let Ok(s) = read_to_string(path) {
+                return parse_cpu_cores(s.trim());
             }
         }
         Default::default()
@@ -125,29 +117,44 @@ impl CGroupSys {
 
     /// None means no limit.
     pub fn cpu_quota(&self) -> Option<f64> {
-        let component = if self.is_v2 { "" } else { "cpu" };
-        if let Some(group) = self.cgroups.get(component) {
-            if let Some((root, mount_point)) = self.mount_points.get(component) {
-                if let Some(path) = build_path(group, root, mount_point) {
-                    if self.is_v2 {
-                        let path = format!("{}/cpu.max", path.to_str().unwrap());
-                        if let Ok(buffer) = read_to_string(path) {
-                            return parse_cpu_quota_v2(buffer.trim());
-                        }
-                    } else {
-                        let path1 = format!("{}/cpu.cfs_quota_us", path.to_str().unwrap());
-                        let path2 = format!("{}/cpu.cfs_period_us", path.to_str().unwrap());
-                        if let (Ok(buffer1), Ok(buffer2)) =
-                            (read_to_string(path1), read_to_string(path2))
-                        {
-                            return parse_cpu_quota_v1(buffer1.trim(), buffer2.trim());
-                        }
+        let (path, v2) = self.controller_path("cpu")?;
+        if v2 {
+            let path = format!("{}/cpu.max", path.to_str().unwrap());
+            if let Ok(buffer) = read_to_string(path) {
+                return parse_cpu_quota_v2(buffer.trim());
+            }
+        } else {
+            let path1 = format!("{}/cpu.cfs_quota_us", path.to_str().unwrap());
+            let path2 = format!("{}/cpu.cfs_period_us", path.to_str().unwrap());
+            if let (Ok(buffer1), Ok(buffer2)) = (read_to_string(path1), read_to_string(path2)) {
+                return parse_cpu_quota_v1(buffer1.trim(), buffer2.trim());
+            }
+        }
+        None
+    }
+
+    /// Resolves the absolute path of the given controller's cgroup directory
+    /// and whether it belongs to the unified (v2) hierarchy. In hybrid setups
+    /// a controller missing from the v1 hierarchies falls back to the unified
+    /// one.
+    fn controller_path(&self, controller: &str) -> Option<(PathBuf, bool)> {
+        let hybrid = [(controller, false), ("", true)];
+        let unified = [("", true)];
+        let candidates: &[(&str, bool)] = if self.is_v2 { &unified } else { &hybrid };
+        let mut found = false;
+        for (component, v2) in candidates {
+            if let Some(group) = self.cgroups.get(*component) {
+                found = true;
+                if let Some((root, mount_point)) = self.mount_points.get(*component) {
+                    if let Some(path) = build_path(group, root, mount_point) {
+                        return Some((path, *v2));
                     }
                 }
-            } else {
-                warn!("cgroup cpu controller found but not mounted.");
             }
         }
+        if found {
+            warn!("cgroup {} controller found but not mounted.", controller);
+        }
         None
     }
 }
@@ -432,6 +439,51 @@ mod tests {
         assert!(cgroup_sys.cpuset_cores().is_empty());
     }
 
+    #[test]
+    fn test_hybrid_mode_fallback_to_unified() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path().to_str().unwrap();
+        std::fs::copy("/proc/self/stat", format!("{}/stat", dir)).unwrap();
+
+        let mut f = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(format!("{}/mountinfo", dir))
+            .unwrap();
+        f.write_all(
+            format!(
+                "30 26 0:27 / /sys/fs/cgroup/cpuset,cpu,cpuacct rw,nosuid,nodev,noexec,relatime shared:11 - cgroup cgroup rw,cpuset,cpu,cpuacct\n\
+                 31 26 0:28 / {}/unified rw,nosuid,nodev,noexec,relatime - cgroup2 cgroup2 rw\n",
+                dir
+            )
+            .as_bytes(),
+        )
+        .unwrap();
+        std::fs::create_dir(format!("{}/unified", dir)).unwrap();
+        std::fs::write(format!("{}/unified/memory.max", dir), b"1073741824\n").unwrap();
+
+        // The memory controller is only enabled in the unified hierarchy.
+        let cgroups = parse_proc_cgroup_v1("3:cpuset,cpu,cpuacct:/\n0::/\n");
+        let mount_points = {
+            let p = Process::new_with_root(PathBuf::from(dir)).unwrap();
+            let mut mount_points = parse_mountinfos_v1(p.mountinfo().unwrap());
+            for (controller, mount) in parse_mountinfos_v2(p.mountinfo().unwrap()) {
+                mount_points.entry(controller).or_insert(mount);
+            }
+            mount_points
+        };
+
+        let cgroup_sys = CGroupSys {
+            cgroups,
+            mount_points,
+            is_v2: false,
+        };
+        // Memory limits are served by the unified hierarchy while the cpu
+        // controller still comes from the v1 one.
+        assert_eq!(cgroup_sys.memory_limit_in_bytes(), Some(1073741824));
+        assert_eq!(cgroup_sys.cpu_quota(), None);
+    }
+
     #[test]
     fn test_mountinfo_with_relative_path() {
         let temp = tempfile::TempDir::new().unwrap();
@@ -44,7 +44,7 @@ impl LinuxStyleCpuTime {
 
 pub use std::io::Result;
 
-pub use imp::cpu_time;
+pub use imp::{cpu_time, thread_cpu_time};
 
 /// A struct to monitor process cpu usage
 #[derive(Clone, Copy)]
@@ -124,6 +124,19 @@ mod imp {
             Err(io::Error::last_os_error())
         }
     }
+
+    pub fn thread_cpu_time() -> io::Result<Duration> {
+        let mut time = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+
+        if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) } == 0 {
+            Ok(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
 }
 
 #[cfg(target_os = "macos")]
@@ -189,6 +202,22 @@ mod imp {
             Err(io::Error::last_os_error())
         }
     }
+
+    pub fn thread_cpu_time() -> io::Result<std::time::Duration> {
+        let mut time = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+
+        if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) } == 0 {
+            Ok(std::time::Duration::new(
+                time.tv_sec as u64,
+                time.tv_nsec as u32,
+            ))
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
 }
 
 #[cfg(target_os = "windows")]
@@ -312,6 +341,15 @@ mod imp {
 
         Ok(Duration::from_nanos(cpu))
     }
+
+    #[inline]
+    pub fn thread_cpu_time() -> io::Result<Duration> {
+        let tid = unsafe { GetCurrentThreadId() };
+        let (kernel_time, user_time) = get_thread_times(tid)?;
+
+        // convert ns
+        Ok(Duration::from_nanos((kernel_time + user_time) * 100))
+    }
 }
 
 #[cfg(test)]
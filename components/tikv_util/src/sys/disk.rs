@@ -1,8 +1,25 @@
 // Copyright 2021 TiKV Project Authors. Licensed under Apache-2.0.
-use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering},
+        Mutex,
+    },
+};
 
 use fail::fail_point;
 pub use kvproto::disk_usage::DiskUsage;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    // Disk usages injected for particular stores, only used by tests to
+    // simulate disk failures without touching the global status.
+    static ref STORE_DISK_STATUS: Mutex<HashMap<u64, DiskUsage>> = Mutex::new(HashMap::new());
+}
+
+// Whether `STORE_DISK_STATUS` contains any injection, checked first so that
+// the common path doesn't pay for the lock.
+static STORE_DISK_STATUS_INJECTED: AtomicBool = AtomicBool::new(false);
 
 // DISK_RESERVED_SPACE means if left space is less than this, tikv will
 // turn to maintenance mode. There are another 2 value derived from this,
@@ -38,7 +55,29 @@ pub fn set_disk_status(status: DiskUsage) {
     DISK_STATUS.store(v, Ordering::Release);
 }
 
+/// Injects a disk usage for the given store. It overrides the global status
+/// until [`clear_disk_status_of_store`] is called.
+pub fn set_disk_status_of_store(store_id: u64, usage: DiskUsage) {
+    let mut status = STORE_DISK_STATUS.lock().unwrap();
+    status.insert(store_id, usage);
+    STORE_DISK_STATUS_INJECTED.store(true, Ordering::Release);
+}
+
+/// Clears the injected disk usage of the given store.
+pub fn clear_disk_status_of_store(store_id: u64) {
+    let mut status = STORE_DISK_STATUS.lock().unwrap();
+    status.remove(&store_id);
+    if status.is_empty() {
+        STORE_DISK_STATUS_INJECTED.store(false, Ordering::Release);
+    }
+}
+
 pub fn get_disk_status(_store_id: u64) -> DiskUsage {
+    if STORE_DISK_STATUS_INJECTED.load(Ordering::Acquire) {
+        if let Some(usage) = STORE_DISK_STATUS.lock().unwrap().get(&_store_id) {
+            return *usage;
+        }
+    }
     fail_point!("disk_almost_full_peer_1", _store_id == 1, |_| {
         DiskUsage::AlmostFull
     });
@@ -3,7 +3,7 @@
 use std::{collections::HashMap, fs::File, io::Read};
 
 /// IoLoad represents current system block devices IO statistics
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct IoLoad {
     /// number of read I/Os processed
     /// units: requests
@@ -0,0 +1,226 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Periodically turns raw block device counters into device load statistics.
+//!
+//! [`DiskStatsCollector`] resolves the block devices backing interesting
+//! paths (typically the data and raft WAL mount points), and on every refresh
+//! derives reads/writes per second, bandwidth, utilization and average queue
+//! depth from the deltas of two [`IoLoad`] snapshots, the same way iostat
+//! does. Consumers like the IO rate controller and the metrics endpoint read
+//! the last computed value instead of sampling the devices themselves.
+
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{Builder, JoinHandle},
+    time::Duration,
+};
+
+use super::{ioload::IoLoad, thread::StdThreadBuildWrapper};
+use crate::time::Instant;
+
+/// The size of a sector reported in `/sys/block/<dev>/stat`, fixed by the
+/// kernel regardless of the real device sector size.
+const SECTOR_SIZE: u64 = 512;
+
+/// Load of one block device derived from two consecutive snapshots.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiskStats {
+    /// read requests completed per second
+    pub reads_per_sec: f64,
+    /// write requests completed per second
+    pub writes_per_sec: f64,
+    /// bytes read per second
+    pub read_bytes_per_sec: f64,
+    /// bytes written per second
+    pub write_bytes_per_sec: f64,
+    /// fraction of wall time the device was busy, in [0, 1]
+    pub util: f64,
+    /// average number of requests in the queue, iostat's avgqu-sz
+    pub queue_depth: f64,
+}
+
+struct State {
+    last_snapshot: HashMap<String, IoLoad>,
+    last_instant: Instant,
+    stats: HashMap<String, DiskStats>,
+}
+
+/// Collects load statistics of the block devices backing registered paths.
+pub struct DiskStatsCollector {
+    // tag (e.g. "kv", "raft") -> block device name (e.g. "nvme0n1").
+    devices: Mutex<HashMap<String, String>>,
+    state: Mutex<State>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Default for DiskStatsCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiskStatsCollector {
+    pub fn new() -> DiskStatsCollector {
+        DiskStatsCollector {
+            devices: Mutex::new(HashMap::new()),
+            state: Mutex::new(State {
+                last_snapshot: IoLoad::snapshot(),
+                last_instant: Instant::now(),
+                stats: HashMap::new(),
+            }),
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Registers the block device backing `path` under `tag`. Returns false
+    /// if the device cannot be resolved, e.g. on unsupported platforms or
+    /// virtual file systems.
+    pub fn register_path(&self, tag: &str, path: &Path) -> bool {
+        match resolve_block_device(path) {
+            Some(device) => {
+                self.devices
+                    .lock()
+                    .unwrap()
+                    .insert(tag.to_owned(), device);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Recomputes the statistics of all registered devices from a fresh
+    /// snapshot.
+    pub fn refresh(&self) {
+        let snapshot = IoLoad::snapshot();
+        let now = Instant::now();
+        let devices = self.devices.lock().unwrap().clone();
+        let mut state = self.state.lock().unwrap();
+        let elapsed = now.saturating_duration_since(state.last_instant);
+        let secs = elapsed.as_secs_f64();
+        if secs <= 0.0 {
+            return;
+        }
+        let mut stats = HashMap::new();
+        for (tag, device) in devices {
+            let (old, new) = match (state.last_snapshot.get(&device), snapshot.get(&device)) {
+                (Some(old), Some(new)) => (old, new),
+                _ => continue,
+            };
+            stats.insert(tag, diff_load(old, new, secs));
+        }
+        state.last_snapshot = snapshot;
+        state.last_instant = now;
+        state.stats = stats;
+    }
+
+    /// Returns the statistics computed by the last refresh, keyed by tag.
+    pub fn stats(&self) -> HashMap<String, DiskStats> {
+        self.state.lock().unwrap().stats.clone()
+    }
+
+    /// Spawns a background thread refreshing the statistics at the given
+    /// interval until the collector is dropped.
+    pub fn start(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let collector = self.clone();
+        let stopped = self.stopped.clone();
+        Builder::new()
+            .name(thd_name!("disk-stats"))
+            .spawn_wrapper(move || {
+                while !stopped.load(Ordering::Relaxed) {
+                    std::thread::sleep(interval);
+                    collector.refresh();
+                }
+            })
+            .unwrap()
+    }
+}
+
+impl Drop for DiskStatsCollector {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Derives the load over an interval of `secs` seconds from two snapshots of
+/// one device, iostat style.
+fn diff_load(old: &IoLoad, new: &IoLoad, secs: f64) -> DiskStats {
+    DiskStats {
+        reads_per_sec: (new.read_io - old.read_io).max(0.0) / secs,
+        writes_per_sec: (new.write_io - old.write_io).max(0.0) / secs,
+        read_bytes_per_sec: (new.read_sectors - old.read_sectors).max(0.0) * SECTOR_SIZE as f64
+            / secs,
+        write_bytes_per_sec: (new.write_sectors - old.write_sectors).max(0.0) * SECTOR_SIZE as f64
+            / secs,
+        // io_ticks and time_in_queue are in milliseconds.
+        util: ((new.io_ticks - old.io_ticks).max(0.0) / (secs * 1000.0)).min(1.0),
+        queue_depth: (new.time_in_queue - old.time_in_queue).max(0.0) / (secs * 1000.0),
+    }
+}
+
+/// Resolves the name of the block device in `/sys/block` that backs `path`.
+#[cfg(target_os = "linux")]
+fn resolve_block_device(path: &Path) -> Option<String> {
+    let mount = mnt::get_mount(path).ok()??;
+    let spec = mount.spec.strip_prefix("/dev/")?;
+    // The mount source is usually a partition (sda1, nvme0n1p1) while the
+    // kernel accounts IO on the parent device, so match by prefix.
+    IoLoad::snapshot()
+        .keys()
+        .filter(|dev| spec.starts_with(dev.as_str()))
+        .max_by_key(|dev| dev.len())
+        .cloned()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn resolve_block_device(_path: &Path) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_load() {
+        let old = IoLoad {
+            read_io: 100.0,
+            read_sectors: 800.0,
+            write_io: 50.0,
+            write_sectors: 400.0,
+            io_ticks: 1000.0,
+            time_in_queue: 1000.0,
+            ..Default::default()
+        };
+        let new = IoLoad {
+            read_io: 300.0,
+            read_sectors: 1800.0,
+            write_io: 150.0,
+            write_sectors: 600.0,
+            io_ticks: 2000.0,
+            time_in_queue: 5000.0,
+            ..Default::default()
+        };
+        let stats = diff_load(&old, &new, 2.0);
+        assert_eq!(stats.reads_per_sec, 100.0);
+        assert_eq!(stats.writes_per_sec, 50.0);
+        assert_eq!(stats.read_bytes_per_sec, 500.0 * SECTOR_SIZE as f64);
+        assert_eq!(stats.write_bytes_per_sec, 100.0 * SECTOR_SIZE as f64);
+        assert_eq!(stats.util, 0.5);
+        assert_eq!(stats.queue_depth, 2.0);
+
+        // Counters wrapping around must not yield negative rates.
+        let stats = diff_load(&new, &old, 2.0);
+        assert_eq!(stats, DiskStats::default());
+    }
+
+    #[test]
+    fn test_register_unknown_path() {
+        let collector = DiskStatsCollector::new();
+        assert!(!collector.register_path("kv", Path::new("/path/not/exists")));
+    }
+}
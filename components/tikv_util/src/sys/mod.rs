@@ -6,6 +6,7 @@ pub mod cpu_time;
 pub mod disk;
 pub mod inspector;
 pub mod ioload;
+pub mod iostats;
 pub mod thread;
 
 // re-export some traits for ease of use
@@ -199,6 +200,17 @@ pub fn path_in_diff_mount_point(_path1: impl AsRef<Path>, _path2: impl AsRef<Pat
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_memory_usage_high_water() {
+        record_global_memory_usage();
+        let mut usage = 0;
+        register_memory_usage_high_water(u64::MAX);
+        assert!(!memory_usage_reaches_high_water(&mut usage));
+        assert_eq!(usage, get_global_memory_usage());
+        register_memory_usage_high_water(0);
+        assert!(memory_usage_reaches_high_water(&mut usage));
+    }
+
     #[test]
     fn test_path_in_diff_mount_point() {
         let (empty_path1, path2) = ("", "/");
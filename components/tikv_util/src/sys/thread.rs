@@ -31,6 +31,37 @@ fn cpu_total(sys_time: i64, user_time: i64) -> f64 {
     (sys_time + user_time) as f64 / ticks_per_second() as f64
 }
 
+/// A NUMA node and the CPUs attached to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumaNode {
+    pub id: usize,
+    pub cpus: Vec<usize>,
+}
+
+/// Parses a kernel cpu list like `0-3,8,10-11` into CPU numbers.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for part in list.split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start: usize = start.parse().ok()?;
+                let end: usize = end.parse().ok()?;
+                if start > end {
+                    return None;
+                }
+                cpus.extend(start..=end);
+            }
+            None => cpus.push(part.parse().ok()?),
+        }
+    }
+    Some(cpus)
+}
+
 #[cfg(target_os = "linux")]
 pub mod linux {
     #[inline]
@@ -148,6 +179,53 @@ mod imp {
         }
     }
 
+    /// Discovers the NUMA topology from sysfs. Nodes are sorted by id.
+    /// Returns an empty vector when the information is unavailable, e.g.
+    /// in some containers.
+    pub fn numa_nodes() -> Vec<super::NumaNode> {
+        let mut nodes = Vec::new();
+        let dir = match fs::read_dir("/sys/devices/system/node") {
+            Ok(dir) => dir,
+            Err(_) => return nodes,
+        };
+        for entry in dir.flatten() {
+            let name = entry.file_name();
+            let id = match name
+                .to_str()
+                .and_then(|name| name.strip_prefix("node"))
+                .and_then(|id| id.parse().ok())
+            {
+                Some(id) => id,
+                None => continue,
+            };
+            let cpulist = match fs::read_to_string(entry.path().join("cpulist")) {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+            if let Some(cpus) = super::parse_cpu_list(cpulist.trim()) {
+                nodes.push(super::NumaNode { id, cpus });
+            }
+        }
+        nodes.sort_by_key(|n| n.id);
+        nodes
+    }
+
+    /// Binds the current thread to the CPUs of the given NUMA node so its
+    /// allocations stay local to that node.
+    pub fn bind_thread_to_node(node: &super::NumaNode) -> io::Result<()> {
+        // Unsafe due to FFI.
+        unsafe {
+            let mut set = std::mem::zeroed::<libc::cpu_set_t>();
+            for cpu in &node.cpus {
+                libc::CPU_SET(*cpu, &mut set);
+            }
+            if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+                return Err(Error::last_os_error());
+            }
+            Ok(())
+        }
+    }
+
     // Sadly the std lib does not have any support for setting `errno`, so we
     // have to implement this ourselves.
     extern "C" {
@@ -298,6 +376,14 @@ mod imp {
     pub fn get_priority() -> io::Result<i32> {
         Ok(0)
     }
+
+    pub fn numa_nodes() -> Vec<super::NumaNode> {
+        Vec::new()
+    }
+
+    pub fn bind_thread_to_node(_node: &super::NumaNode) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 #[cfg(not(any(target_os = "linux", target_os = "macos")))]
@@ -349,6 +435,14 @@ mod imp {
     pub fn get_priority() -> io::Result<i32> {
         Ok(0)
     }
+
+    pub fn numa_nodes() -> Vec<super::NumaNode> {
+        Vec::new()
+    }
+
+    pub fn bind_thread_to_node(_node: &super::NumaNode) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 pub use self::imp::*;
@@ -505,6 +599,26 @@ mod tests {
     use super::*;
     use crate::yatp_pool::{DefaultTicker, YatpPoolBuilder};
 
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0"), Some(vec![0]));
+        assert_eq!(parse_cpu_list("0-3"), Some(vec![0, 1, 2, 3]));
+        assert_eq!(parse_cpu_list("0-1,4,6-7"), Some(vec![0, 1, 4, 6, 7]));
+        assert_eq!(parse_cpu_list(""), Some(vec![]));
+        assert_eq!(parse_cpu_list("3-1"), None);
+        assert_eq!(parse_cpu_list("a"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_bind_thread_to_node() {
+        // Machines without exposed NUMA information report no nodes.
+        for node in numa_nodes() {
+            assert!(!node.cpus.is_empty());
+            bind_thread_to_node(&node).unwrap();
+        }
+    }
+
     #[test]
     fn test_thread_id() {
         let id = thread_id();
@@ -6,7 +6,10 @@ use std::{
     cell::RefCell,
     cmp::Ordering,
     ops::{Add, AddAssign, Sub, SubAssign},
-    sync::mpsc::{self, Sender},
+    sync::{
+        atomic::{AtomicU64, Ordering as AtomicOrdering},
+        mpsc::{self, Sender},
+    },
     thread::{self, Builder, JoinHandle},
     time::{SystemTime, UNIX_EPOCH},
 };
@@ -200,12 +203,41 @@ impl Drop for Monitor {
     }
 }
 
-use self::inner::monotonic_coarse_now;
-pub use self::inner::monotonic_now;
-/// Returns the monotonic raw time since some unspecified starting point.
-pub use self::inner::monotonic_raw_now;
 use crate::sys::thread::StdThreadBuildWrapper;
 
+// Nanoseconds the monotonic clocks have been advanced by, only written in
+// tests to expire leases and timeouts without sleeping.
+static CLOCK_ADVANCE_NS: AtomicU64 = AtomicU64::new(0);
+
+/// Advances the time returned by `monotonic_*_now` by `duration`.
+///
+/// It is only for tests: the clocks never go backwards, so jumping them
+/// forward lets lease or timeout expirations be triggered deterministically
+/// instead of sleeping through them. The advancement is process-wide and
+/// can't be undone.
+pub fn advance_monotonic_clock(duration: Duration) {
+    CLOCK_ADVANCE_NS.fetch_add(duration.as_nanos() as u64, AtomicOrdering::Release);
+}
+
+#[inline]
+fn clock_advance() -> TimeDuration {
+    TimeDuration::nanoseconds(CLOCK_ADVANCE_NS.load(AtomicOrdering::Acquire) as i64)
+}
+
+/// Returns the monotonic time since some unspecified starting point.
+pub fn monotonic_now() -> Timespec {
+    inner::monotonic_now() + clock_advance()
+}
+
+/// Returns the monotonic raw time since some unspecified starting point.
+pub fn monotonic_raw_now() -> Timespec {
+    inner::monotonic_raw_now() + clock_advance()
+}
+
+fn monotonic_coarse_now() -> Timespec {
+    inner::monotonic_coarse_now() + clock_advance()
+}
+
 const NANOSECONDS_PER_SECOND: u64 = 1_000_000_000;
 const MILLISECOND_PER_SECOND: i64 = 1_000;
 const NANOSECONDS_PER_MILLISECOND: i64 = 1_000_000;
@@ -573,6 +605,18 @@ mod tests {
         assert_eq!(jumped.load(Ordering::SeqCst), true);
     }
 
+    #[test]
+    fn test_advance_monotonic_clock() {
+        let before = monotonic_raw_now();
+        advance_monotonic_clock(Duration::from_millis(100));
+        let after = monotonic_raw_now();
+        assert!(after - before >= TimeDuration::milliseconds(100));
+        // All monotonic clocks share the advancement.
+        assert!(monotonic_now() - before >= TimeDuration::milliseconds(100));
+        // The coarse clock may lag behind the raw clock by a tick.
+        assert!(monotonic_coarse_now() - before >= TimeDuration::milliseconds(90));
+    }
+
     #[test]
     fn test_duration_to() {
         let tbl = vec![0, 100, 1_000, 5_000, 9999, 1_000_000, 1_000_000_000];
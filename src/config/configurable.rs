@@ -4,7 +4,7 @@ use std::{error::Error, io::Write};
 
 use engine_rocks::RocksEngine;
 use engine_traits::{
-    CachedTablet, CfOptions, CfOptionsExt, DbOptions, DbOptionsExt, TabletRegistry, CF_DEFAULT,
+    CachedTablet, CfOptions, CfOptionsExt, DbOptions, DbOptionsExt, MiscExt, TabletRegistry,
 };
 
 pub type ConfigRes = Result<(), Box<dyn Error>>;
@@ -69,9 +69,7 @@ impl ConfigurableDb for RocksEngine {
     }
 
     fn set_shared_block_cache_capacity(&self, capacity: usize) -> ConfigRes {
-        let opt = self.get_options_cf(CF_DEFAULT).unwrap(); // FIXME unwrap
-        opt.set_block_cache_capacity(capacity as u64)
-            .map_err(Box::from)
+        MiscExt::set_block_cache_capacity(self, capacity as u64).map_err(Box::from)
     }
 
     fn set_high_priority_background_threads(&self, n: i32, allow_reduce: bool) -> ConfigRes {
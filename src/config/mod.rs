@@ -2793,6 +2793,9 @@ pub struct BackupConfig {
     pub num_threads: usize,
     pub batch_size: usize,
     pub sst_max_size: ReadableSize,
+    // Speed limit in bytes per second applied to backup requests that do not
+    // carry their own rate limit. 0 means no limit.
+    pub rate_limit: ReadableSize,
     pub enable_auto_tune: bool,
     pub auto_tune_remain_threads: usize,
     pub auto_tune_refresh_interval: ReadableDuration,
@@ -2843,6 +2846,7 @@ impl Default for BackupConfig {
             num_threads: (cpu_num * 0.5).clamp(1.0, 8.0) as usize,
             batch_size: 8,
             sst_max_size: default_coprocessor.region_max_size(),
+            rate_limit: ReadableSize(0),
             enable_auto_tune: true,
             auto_tune_remain_threads: (cpu_num * 0.2).round() as usize,
             auto_tune_refresh_interval: ReadableDuration::secs(60),
@@ -5157,6 +5161,55 @@ mod tests {
         assert_eq!(cfg_from_file.raftdb.wal_dir, s1);
     }
 
+    #[test]
+    fn test_update_without_persist() {
+        let (cfg, _dir) = TikvConfig::with_tmp().unwrap();
+        let cfg_path = cfg.cfg_path.clone();
+        let cfg_controller = ConfigController::new(cfg);
+
+        // Changes applied by `update` are written back to the config file.
+        cfg_controller
+            .update_config("raftstore.raft-log-gc-threshold", "100")
+            .unwrap();
+        assert_eq!(
+            cfg_controller
+                .get_current()
+                .raft_store
+                .raft_log_gc_threshold,
+            100
+        );
+        let persisted = TikvConfig::from_file(Path::new(&cfg_path), None).unwrap();
+        assert_eq!(persisted.raft_store.raft_log_gc_threshold, 100);
+
+        // `update_without_persist` only changes the running config and
+        // leaves the config file untouched.
+        let mut change = HashMap::new();
+        change.insert(
+            "raftstore.raft-log-gc-threshold".to_owned(),
+            "200".to_owned(),
+        );
+        cfg_controller.update_without_persist(change).unwrap();
+        assert_eq!(
+            cfg_controller
+                .get_current()
+                .raft_store
+                .raft_log_gc_threshold,
+            200
+        );
+        let persisted = TikvConfig::from_file(Path::new(&cfg_path), None).unwrap();
+        assert_eq!(persisted.raft_store.raft_log_gc_threshold, 100);
+
+        // Reloading the config file rolls back the unpersisted change.
+        cfg_controller.update_from_toml_file().unwrap();
+        assert_eq!(
+            cfg_controller
+                .get_current()
+                .raft_store
+                .raft_log_gc_threshold,
+            100
+        );
+    }
+
     #[test]
     fn test_flatten_cfg() {
         let mut cfg = TikvConfig::default();
@@ -35,7 +35,7 @@ mod tracker;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-pub use checksum::checksum_crc64_xor;
+pub use checksum::{checksum_crc64_xor, checksum_crc64_xor_seeded};
 use engine_traits::PerfLevel;
 use kvproto::{coprocessor as coppb, kvrpcpb};
 use lazy_static::lazy_static;
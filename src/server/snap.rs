@@ -318,6 +318,15 @@ fn recv_snap<R: RaftExtension + 'static>(
         }
         let context_key = context.key.clone();
         let total_size = context.file.as_ref().unwrap().total_size();
+        let resumed_offset = context.file.as_ref().unwrap().resumed_offset();
+        if resumed_offset > 0 {
+            info!(
+                "resuming snapshot receive from a previous attempt";
+                "snap_key" => %context_key,
+                "resumed_offset" => resumed_offset,
+                "total_size" => total_size,
+            );
+        }
         SNAP_LIMIT_TRANSPORT_BYTES_COUNTER_STATIC
             .recv
             .inc_by(total_size);
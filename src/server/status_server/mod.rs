@@ -136,14 +136,16 @@ where
         let use_jeprof = query_pairs.get("jeprof").map(|x| x.as_ref()) == Some("true");
 
         let result = {
-            let path = match dump_one_heap_profile() {
-                Ok(path) => path,
+            // Keep the temp file alive until its content has been read.
+            let file = match dump_one_heap_profile() {
+                Ok(file) => file,
                 Err(e) => return Ok(make_response(StatusCode::INTERNAL_SERVER_ERROR, e)),
             };
+            let path = file.path().to_str().unwrap();
             if use_jeprof {
-                jeprof_heap_profile(path.to_str().unwrap())
+                jeprof_heap_profile(path)
             } else {
-                read_file(path.to_str().unwrap())
+                read_file(path)
             }
         };
 
@@ -427,6 +429,27 @@ where
         }
     }
 
+    async fn get_store_status(
+        cfg_controller: &ConfigController,
+        grpc_service_mgr: &GrpcServiceManager,
+    ) -> hyper::Result<Response<Body>> {
+        let cfg = cfg_controller.get_current();
+        let summary = serde_json::json!({
+            "version": crate::tikv_build_version(),
+            "engine_type": cfg_controller.get_engine_type(),
+            "api_version": cfg.storage.api_version,
+            "log_level": cfg.log.level,
+            "grpc_paused": grpc_service_mgr.is_paused(),
+        });
+        Ok(match serde_json::to_string(&summary) {
+            Ok(json) => Response::builder()
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(json))
+                .unwrap(),
+            Err(_) => make_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error"),
+        })
+    }
+
     async fn get_engine_type(cfg_controller: &ConfigController) -> hyper::Result<Response<Body>> {
         let engine_type = cfg_controller.get_engine_type();
         let response = Response::builder()
@@ -684,6 +707,9 @@ where
                             (Method::GET, "/engine_type") => {
                                 Self::get_engine_type(&cfg_controller).await
                             }
+                            (Method::GET, "/store_status") => {
+                                Self::get_store_status(&cfg_controller, &grpc_service_mgr).await
+                            }
                             // This interface is used for configuration file hosting scenarios,
                             // TiKV will not update configuration files, and this interface will
                             // silently ignore configration items that cannot be updated online,
@@ -1110,6 +1136,7 @@ mod tests {
     use openssl::ssl::{SslConnector, SslFiletype, SslMethod};
     use raftstore::store::region_meta::RegionMeta;
     use security::SecurityConfig;
+    use serde_json::Value;
     use service::service_manager::GrpcServiceManager;
     use test_util::new_security_cfg;
     use tikv_kv::RaftExtension;
@@ -1817,6 +1844,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_store_status() {
+        let mut status_server = StatusServer::new(
+            1,
+            ConfigController::new(TikvConfig::default()),
+            Arc::new(SecurityConfig::default()),
+            MockRouter,
+            None,
+            GrpcServiceManager::dummy(),
+        )
+        .unwrap();
+        let addr = "127.0.0.1:0".to_owned();
+        let _ = status_server.start(addr);
+        let client = Client::new();
+        let uri = Uri::builder()
+            .scheme("http")
+            .authority(status_server.listening_addr().to_string().as_str())
+            .path_and_query("/store_status")
+            .build()
+            .unwrap();
+
+        let handle = status_server.thread_pool.spawn(async move {
+            let res = client.get(uri).await.unwrap();
+            assert_eq!(res.status(), StatusCode::OK);
+            let body_bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+            let summary: Value = serde_json::from_slice(body_bytes.as_ref()).unwrap();
+            assert_eq!(
+                summary["version"].as_str().unwrap(),
+                crate::tikv_build_version()
+            );
+            assert_eq!(summary["engine_type"].as_str().unwrap(), "raft-kv");
+            assert!(!summary["grpc_paused"].as_bool().unwrap());
+        });
+        block_on(handle).unwrap();
+        status_server.stop();
+    }
+
     #[test]
     fn test_control_grpc_service() {
         let mut multi_rocks_cfg = TikvConfig::default();
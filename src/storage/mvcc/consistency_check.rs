@@ -479,6 +479,43 @@ mod tests {
         assert_ne!(checksums[0], checksums[2]);
     }
 
+    #[test]
+    fn test_mvcc_checksum_ignores_gc_able_versions() {
+        use engine_traits::SyncMutable;
+
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        must_prewrite_put(&mut engine, b"zAAAAA", b"value", b"PRIMARY", 100);
+        must_commit(&mut engine, b"zAAAAA", 100, 101);
+        must_prewrite_put(&mut engine, b"zBBBBB", b"value", b"PRIMARY", 200);
+        must_commit(&mut engine, b"zBBBBB", 200, 201);
+
+        let raw = engine.get_rocksdb();
+        let compute = |raw: &engine_rocks::RocksEngine| {
+            let mut scanner = MvccInfoScanner::new(
+                |cf, opts| raw.iterator_opt(cf, opts).map_err(|e| box_err!(e)),
+                Some(&keys::data_key(b"")),
+                Some(&keys::data_end_key(b"")),
+                MvccChecksum::new(150),
+            )
+            .unwrap();
+            while scanner.next_item().unwrap().is_some() {}
+            scanner.observer.digest.finalize()
+        };
+        let before = compute(&raw);
+
+        // Physically remove the version below the safe point, like a GC run
+        // on one replica would. The hash must not change.
+        let key = keys::data_key(Key::from_raw(b"zAAAAA").append_ts(101.into()).as_encoded());
+        raw.delete_cf(CF_WRITE, &key).unwrap();
+        let after = compute(&raw);
+        assert_eq!(before, after);
+
+        // Versions above the safe point do affect the hash.
+        let key = keys::data_key(Key::from_raw(b"zBBBBB").append_ts(201.into()).as_encoded());
+        raw.delete_cf(CF_WRITE, &key).unwrap();
+        assert_ne!(compute(&raw), after);
+    }
+
     #[test]
     fn test_mvcc_info_collector() {
         use engine_test::ctor::{CfOptions, DbOptions};
@@ -467,4 +467,61 @@ pub mod tests {
         }
         must_unlocked(&mut engine, b"k1");
     }
+
+    #[test]
+    fn test_check_secondary_locks_multiple_keys() {
+        let mut engine = TestEngineBuilder::new().build().unwrap();
+        let mut engine_clone = engine.clone();
+        let ctx = Context::default();
+        let cm = ConcurrencyManager::new(1.into());
+
+        let mut check_secondary = |keys: &[&[u8]], ts: u64| {
+            let snapshot = engine_clone.snapshot(Default::default()).unwrap();
+            let command = crate::storage::txn::commands::CheckSecondaryLocks {
+                ctx: Default::default(),
+                keys: keys.iter().map(|k| Key::from_raw(k)).collect(),
+                start_ts: ts.into(),
+                deadline: Deadline::from_now(DEFAULT_EXECUTION_DURATION_LIMIT),
+            };
+            let result = command
+                .process_write(
+                    snapshot,
+                    WriteContext {
+                        lock_mgr: &MockLockManager::new(),
+                        concurrency_manager: cm.clone(),
+                        extra_op: Default::default(),
+                        statistics: &mut Default::default(),
+                        async_apply_prewrite: false,
+                        raw_ext: None,
+                        txn_status_cache: &TxnStatusCache::new_for_test(),
+                    },
+                )
+                .unwrap();
+            let modifies = result.to_be_write.modifies;
+            if let ProcessResult::SecondaryLocksStatus { status } = result.pr {
+                (status, modifies)
+            } else {
+                unreachable!();
+            }
+        };
+
+        must_prewrite_lock(&mut engine, b"k1", b"key", 1);
+        must_prewrite_lock(&mut engine, b"k2", b"key", 1);
+
+        // All secondary locks exist, so all lock information is returned.
+        let (status, _) = check_secondary(&[b"k1", b"k2"], 1);
+        match status {
+            SecondaryLocksStatus::Locked(locks) => assert_eq!(locks.len(), 2),
+            res => panic!("unexpected lock status: {:?}", res),
+        }
+
+        // A missing lock determines the transaction status, so checking stops
+        // there and a protected rollback is written for the missing key only.
+        let (status, modifies) = check_secondary(&[b"k1", b"k3", b"k2"], 1);
+        assert_eq!(status, SecondaryLocksStatus::RolledBack);
+        write(&mut engine, &ctx, modifies);
+        must_get_rollback_protected(&mut engine, b"k3", 1, true);
+        must_locked(&mut engine, b"k1", 1);
+        must_locked(&mut engine, b"k2", 1);
+    }
 }
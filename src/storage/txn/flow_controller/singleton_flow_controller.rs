@@ -1108,6 +1108,10 @@ pub(super) mod tests {
                 self.0.pending_compaction_bytes.load(Ordering::Relaxed),
             ))
         }
+
+        fn get_cf_is_write_stalled(&self, _cf: &str) -> Result<Option<bool>> {
+            Ok(Some(false))
+        }
     }
 
     pub fn send_flow_info(tx: &mpsc::SyncSender<FlowInfo>, region_id: u64) {
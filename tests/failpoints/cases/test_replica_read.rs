@@ -11,6 +11,7 @@ use engine_traits::RaftEngineReadOnly;
 use futures::executor::block_on;
 use kvproto::raft_serverpb::{PeerState, RaftMessage, RegionLocalState};
 use raft::eraftpb::MessageType;
+use raftstore::store::Callback;
 use test_raftstore::*;
 use test_raftstore_macro::test_case;
 use tikv::storage::config::EngineType;
@@ -814,3 +815,64 @@ fn test_read_index_lock_checking_on_false_leader() {
     let resp = block_on_timeout(resp.as_mut(), Duration::from_secs(2)).unwrap();
     assert!(resp.get_header().has_error());
 }
+
+/// Test that a follower which has applied `PrepareMerge` does not serve
+/// replica reads until the merge is finished or rolled back, as the data of
+/// the source region may become stale once the target region takes over the
+/// range.
+#[test]
+fn test_replica_read_during_pending_merge() {
+    let mut cluster = new_node_cluster(0, 3);
+    configure_for_merge(&mut cluster.cfg);
+    configure_for_lease_read(&mut cluster.cfg, Some(50), Some(10_000));
+    cluster.run();
+
+    cluster.must_put(b"k1", b"v1");
+    cluster.must_put(b"k3", b"v3");
+    let pd_client = Arc::clone(&cluster.pd_client);
+    let region = pd_client.get_region(b"k1").unwrap();
+    cluster.must_split(&region, b"k2");
+    let left = pd_client.get_region(b"k1").unwrap();
+    let right = pd_client.get_region(b"k3").unwrap();
+
+    let left_peer_1 = find_peer(&left, 1).unwrap().clone();
+    cluster.must_transfer_leader(left.get_id(), left_peer_1);
+    must_get_equal(&cluster.get_engine(3), b"k1", b"v1");
+
+    // Pause the merge after `PrepareMerge` is applied.
+    let schedule_merge_fp = "on_schedule_merge";
+    fail::cfg(schedule_merge_fp, "return()").unwrap();
+    let (tx, rx) = channel::unbounded();
+    fail::cfg_callback("on_apply_res_prepare_merge", move || {
+        tx.send(()).unwrap();
+    })
+    .unwrap();
+    cluster.merge_region(left.get_id(), right.get_id(), Callback::None);
+    // Wait until all the peers of the source region have applied
+    // `PrepareMerge`.
+    for _ in 0..3 {
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+
+    // A replica read on a follower of the source region must be blocked while
+    // the merge is pending.
+    let left_peer_3 = find_peer(&left, 3).unwrap().clone();
+    let mut request = new_request(
+        left.get_id(),
+        left.get_region_epoch().clone(),
+        vec![new_get_cf_cmd("default", b"k1")],
+        false,
+    );
+    request.mut_header().set_peer(left_peer_3);
+    request.mut_header().set_replica_read(true);
+    let mut rx = async_command_on_node(&mut cluster, 3, request);
+    block_on_timeout(rx.as_mut(), Duration::from_millis(500)).unwrap_err();
+
+    // Let the merge finish, the pending read must be notified with an error
+    // instead of returning stale data.
+    fail::remove("on_apply_res_prepare_merge");
+    fail::remove(schedule_merge_fp);
+    pd_client.must_merge(left.get_id(), right.get_id());
+    let resp = block_on_timeout(rx.as_mut(), Duration::from_secs(5)).unwrap();
+    assert!(resp.get_header().has_error());
+}
@@ -296,3 +296,70 @@ fn test_node_compact_reserve_max_ticks() {
     let mut cluster = new_node_cluster(0, count);
     test_compact_reserve_max_ticks(&mut cluster);
 }
+
+fn test_force_compact_log<T: Simulator>(cluster: &mut Cluster<T>) {
+    // Disable all automatic GC policies so only the force-compact message
+    // can advance the truncated state.
+    cluster.cfg.raft_store.raft_log_gc_count_limit = Some(100000);
+    cluster.cfg.raft_store.raft_log_gc_threshold = 100000;
+    cluster.cfg.raft_store.raft_log_gc_size_limit = Some(ReadableSize::mb(20));
+    cluster.cfg.raft_store.raft_log_reserve_max_ticks = 100000;
+    cluster.run();
+
+    cluster.must_put(b"k1", b"v1");
+
+    let mut before_states = HashMap::default();
+    for (&id, engines) in &cluster.engines {
+        must_get_equal(&engines.kv, b"k1", b"v1");
+        let mut state: RaftApplyState = get_raft_msg_or_default(engines, &keys::apply_state_key(1));
+        let state = state.take_truncated_state();
+        // compact should not start
+        assert_eq!(RAFT_INIT_LOG_INDEX, state.get_index());
+        assert_eq!(RAFT_INIT_LOG_TERM, state.get_term());
+        before_states.insert(id, state);
+    }
+
+    for i in 1..60 {
+        let k = i.to_string().into_bytes();
+        let v = k.clone();
+        cluster.must_put(&k, &v);
+    }
+
+    // wait log gc.
+    sleep_ms(500);
+
+    // No limit has been reached, so nothing should be compacted.
+    for (&id, engines) in &cluster.engines {
+        let mut state: RaftApplyState = get_raft_msg_or_default(engines, &keys::apply_state_key(1));
+        let after_state = state.take_truncated_state();
+        let before_state = &before_states[&id];
+        assert_eq!(after_state.get_index(), before_state.get_index());
+    }
+
+    cluster.force_compact_raft_logs(1);
+
+    for _ in 0..50 {
+        sleep_ms(100);
+        if check_compacted(
+            &cluster.engines,
+            &before_states,
+            1,
+            false, // must_compacted
+        ) {
+            return;
+        }
+    }
+    check_compacted(
+        &cluster.engines,
+        &before_states,
+        1,
+        true, // must_compacted
+    );
+}
+
+#[test]
+fn test_node_force_compact_log() {
+    let count = 5;
+    let mut cluster = new_node_cluster(0, count);
+    test_force_compact_log(&mut cluster);
+}
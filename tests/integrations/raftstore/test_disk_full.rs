@@ -0,0 +1,40 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::time::Duration;
+
+use kvproto::disk_usage::DiskUsage;
+use test_raftstore::*;
+use tikv_util::future::block_on_timeout;
+
+fn assert_disk_full(resp: &kvproto::raft_cmdpb::RaftCmdResponse) {
+    assert!(resp.get_header().get_error().has_disk_full());
+}
+
+// Tests injecting disk statuses per store without fail points.
+#[test]
+fn test_disk_status_injection() {
+    let mut cluster = new_node_cluster(0, 3);
+    cluster.pd_client.disable_default_operator();
+    cluster.run();
+
+    // To ensure all replicas are not pending.
+    cluster.must_put(b"k1", b"v1");
+    must_get_equal(&cluster.get_engine(1), b"k1", b"v1");
+    must_get_equal(&cluster.get_engine(2), b"k1", b"v1");
+    must_get_equal(&cluster.get_engine(3), b"k1", b"v1");
+    cluster.must_transfer_leader(1, new_peer(1, 1));
+
+    // Normal proposals won't be allowed when the leader's disk is full.
+    cluster.set_disk_usage(1, DiskUsage::AlreadyFull);
+    let old_last_index = cluster.raft_local_state(1, 1).last_index;
+    let rx = cluster.async_put(b"k2", b"v2").unwrap();
+    assert_disk_full(&block_on_timeout(rx, Duration::from_secs(2)).unwrap());
+    let new_last_index = cluster.raft_local_state(1, 1).last_index;
+    assert_eq!(old_last_index, new_last_index);
+
+    // Writes succeed again once the disk recovers.
+    cluster.clear_disk_usage(1);
+    cluster.must_transfer_leader(1, new_peer(1, 1));
+    cluster.must_put(b"k2", b"v2");
+    must_get_equal(&cluster.get_engine(1), b"k2", b"v2");
+}
@@ -128,6 +128,50 @@ fn test_node_renew_lease() {
     test_renew_lease!(cluster);
 }
 
+// Test whether the leader lease can be expired deterministically by advancing
+// the monotonic clock instead of sleeping through it.
+#[test]
+fn test_node_lease_expire_by_advancing_clock() {
+    let mut cluster = new_node_cluster(0, 3);
+    // Avoid triggering the log compaction in this test case.
+    cluster.cfg.raft_store.raft_log_gc_threshold = 100;
+    // Use large election timeout to make leadership stable.
+    configure_for_lease_read(&mut cluster.cfg, Some(50), Some(10_000));
+    // Override max leader lease to 2 seconds.
+    let max_lease = Duration::from_secs(2);
+    cluster.cfg.raft_store.raft_store_max_leader_lease = ReadableDuration(max_lease);
+    cluster.cfg.raft_store.check_leader_lease_interval = ReadableDuration::hours(10);
+    cluster.cfg.raft_store.renew_leader_lease_advance_duration = ReadableDuration::secs(0);
+
+    cluster.pd_client.disable_default_operator();
+    let region_id = cluster.run_conf_change();
+    let key = b"k";
+    cluster.must_put(key, b"v0");
+    for id in 2..=3 {
+        cluster.pd_client.must_add_peer(region_id, new_peer(id, id));
+        must_get_equal(&cluster.get_engine(id), key, b"v0");
+    }
+
+    let peer = new_peer(1, 1);
+    let region = cluster.get_region(key);
+    cluster.must_transfer_leader(region_id, peer.clone());
+
+    let detector = LeaseReadFilter::default();
+    cluster.add_send_filter(CloneFilterFactory(detector.clone()));
+
+    // The lease is valid, so the leader serves the read locally.
+    must_read_on_peer(&mut cluster, peer.clone(), region.clone(), key, b"v0");
+    assert_eq!(detector.ctx.rl().len(), 0);
+
+    // Expire the lease without sleeping.
+    cluster.advance_clock(max_lease);
+
+    // The leader has to fall back to an index read and renews its lease.
+    must_read_on_peer(&mut cluster, peer.clone(), region, key, b"v0");
+    assert_eq!(cluster.leader_of_region(region_id), Some(peer));
+    assert_eq!(detector.ctx.rl().len(), 1);
+}
+
 // Test lease reads when the lease has expired.
 // If the leader lease has expired, there may be new leader elected and
 // the old leader will fail to renew its lease.
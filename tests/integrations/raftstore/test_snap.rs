@@ -113,6 +113,42 @@ fn test_server_huge_snapshot_multi_files() {
     test_huge_snapshot(&mut cluster, 1024 * 1024);
 }
 
+// Snapshots tampered with in flight must fail the integrity check on the
+// receiving store and be sent again.
+fn test_corrupt_snapshot_resent(tamper: SnapshotTamper) {
+    let mut cluster = new_node_cluster(0, 3);
+    configure_for_snapshot(&mut cluster.cfg);
+    let pd_client = Arc::clone(&cluster.pd_client);
+    pd_client.disable_default_operator();
+    let r1 = cluster.run_conf_change();
+
+    for i in 0..100 {
+        cluster.must_put(format!("k{:03}", i).as_bytes(), b"v");
+    }
+
+    let applied = Arc::new(AtomicBool::new(false));
+    cluster.add_send_filter_on_node(
+        1,
+        Box::new(CorruptSnapshotFilter::new(tamper, applied.clone())),
+    );
+
+    // The first snapshot is corrupted and rejected, the resent one must
+    // bring the new peer up.
+    pd_client.must_add_peer(r1, new_peer(2, 2));
+    must_get_equal(&cluster.get_engine(2), b"k099", b"v");
+    assert!(applied.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_node_corrupt_snapshot_checksum() {
+    test_corrupt_snapshot_resent(SnapshotTamper::Checksum);
+}
+
+#[test]
+fn test_node_corrupt_snapshot_truncated() {
+    test_corrupt_snapshot_resent(SnapshotTamper::Truncate);
+}
+
 fn test_server_snap_gc_internal(version: &str) {
     let mut cluster = new_server_cluster(0, 3);
     configure_for_snapshot(&mut cluster.cfg);
@@ -17,7 +17,7 @@ use kvproto::{
 use pd_client::PdClient;
 use raft::eraftpb::MessageType;
 use raftstore::{
-    store::{Bucket, BucketRange, Callback, WriteResponse},
+    store::{Bucket, BucketRange, Callback, CasualMessage, CasualRouter, WriteResponse},
     Result,
 };
 use raftstore_v2::router::QueryResult;
@@ -152,6 +152,57 @@ fn test_server_split_region_twice() {
     rx1.recv_timeout(Duration::from_secs(5)).unwrap();
 }
 
+// A split request without any split key asks the leader to pick the
+// approximate middle key of the region from SST properties by itself.
+#[test]
+fn test_node_split_region_without_split_key() {
+    let mut cluster = new_node_cluster(0, 1);
+    cluster.run();
+    let pd_client = Arc::clone(&cluster.pd_client);
+
+    let big_value = vec![b'v'; 256];
+    for i in 0..100 {
+        let k = format!("key_{:03}", i).into_bytes();
+        cluster.must_put(&k, &big_value);
+        // Flush for every key so that the size properties are fine-grained
+        // enough to locate a middle key.
+        cluster.must_flush_cf(CF_DEFAULT, true);
+    }
+
+    let region = pd_client.get_region(b"key_000").unwrap();
+    let (tx, rx) = channel();
+    let c = Box::new(move |write_resp: WriteResponse| {
+        let mut resp = write_resp.response;
+        assert!(!resp.get_header().has_error(), "{:?}", resp);
+        let regions: Vec<_> = resp.mut_admin_response().mut_splits().take_regions().into();
+        assert_eq!(regions.len(), 2);
+        tx.send((regions[0].clone(), regions[1].clone())).unwrap();
+    });
+    let leader = cluster.leader_of_region(region.get_id()).unwrap();
+    let router = cluster.sim.rl().get_router(leader.get_store_id()).unwrap();
+    CasualRouter::send(
+        &router,
+        region.get_id(),
+        CasualMessage::SplitRegion {
+            region_epoch: region.get_region_epoch().clone(),
+            split_keys: vec![],
+            callback: Callback::write(c),
+            source: "test".into(),
+            share_source_region_size: false,
+        },
+    )
+    .unwrap();
+
+    let (left, right) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(left.get_start_key(), region.get_start_key());
+    assert_eq!(right.get_end_key(), region.get_end_key());
+    assert_eq!(left.get_end_key(), right.get_start_key());
+    // The server-chosen split key must fall inside the original key range.
+    assert!(!left.get_end_key().is_empty());
+    cluster.must_put(b"key_000", b"v");
+    cluster.must_put(b"key_099", b"v");
+}
+
 #[test_case(test_raftstore::new_node_cluster)]
 #[test_case(test_raftstore::new_server_cluster)]
 #[test_case(test_raftstore::new_incompatible_node_cluster)]
@@ -1,5 +1,7 @@
 // Copyright 2016 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::time::Duration;
+
 use test_raftstore::*;
 
 fn test_partition_write<T: Simulator>(cluster: &mut Cluster<T>) {
@@ -48,6 +50,34 @@ fn test_server_partition_write() {
     test_partition_write(&mut cluster);
 }
 
+#[test]
+fn test_node_partition_plan() {
+    let mut cluster = new_node_cluster(0, 3);
+    cluster.run();
+
+    let (key, value) = (b"k1", b"v1");
+    cluster.must_put(key, value);
+    let region_id = cluster.get_region_id(key);
+    cluster.must_transfer_leader(region_id, new_peer(1, 1));
+
+    // Cut store 1 off from the majority, the partition heals by itself.
+    cluster.add_send_filter(
+        PartitionPlan::new()
+            .cut(vec![1], vec![2, 3])
+            .heal_after(Duration::from_secs(2)),
+    );
+    cluster.reset_leader_of_region(region_id);
+    cluster.must_put(b"k2", b"v2");
+    assert_ne!(cluster.leader_of_region(region_id).unwrap().get_id(), 1);
+
+    // After the heal deadline the old leader catches up without any filter
+    // being cleared by hand.
+    must_get_equal(&cluster.get_engine(1), b"k2", b"v2");
+    cluster.must_transfer_leader(region_id, new_peer(1, 1));
+    cluster.must_put(b"k3", b"v3");
+    cluster.clear_send_filters();
+}
+
 #[test]
 fn test_secure_connect() {
     let mut cluster = new_server_cluster(0, 3);